@@ -0,0 +1,16 @@
+// Deletes the resource and reloads the page, or goes to `redirectTo` when the current page shows
+// the deleted resource. On failure the message of the error page is shown.
+async function deleteResource(url, redirectTo) {
+  const resp = await fetch(url, { method: 'DELETE' });
+
+  if (resp.status !== 200) {
+    const page = new DOMParser().parseFromString(await resp.text(), 'text/html');
+    return alert(page.getElementById('error-message').textContent);
+  }
+
+  if (redirectTo) {
+    return window.location.assign(redirectTo);
+  }
+
+  window.location.reload();
+}
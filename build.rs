@@ -0,0 +1,4 @@
+fn main() {
+    // Release builds ship the templates inside the binary, see `view::Views`.
+    minijinja_embed::embed_templates!("src/view", &[".jinja"]);
+}
@@ -0,0 +1,5 @@
+-- Add migration script here
+
+DROP TABLE subject_course;
+
+DROP TABLE subject;
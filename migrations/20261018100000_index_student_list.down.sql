@@ -0,0 +1,6 @@
+DROP INDEX student_course_id_idx;
+DROP INDEX student_created_at_idx;
+DROP INDEX student_email_idx;
+DROP INDEX student_name_idx;
+
+ALTER TABLE student ALTER COLUMN created_at DROP NOT NULL;
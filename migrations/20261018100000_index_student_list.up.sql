@@ -0,0 +1,8 @@
+-- Keyset pagination compares the sort keys, which must not be NULL
+UPDATE student SET created_at = CURRENT_TIMESTAMP WHERE created_at IS NULL;
+ALTER TABLE student ALTER COLUMN created_at SET NOT NULL;
+
+CREATE INDEX student_name_idx ON student (first_name, last_name, id);
+CREATE INDEX student_email_idx ON student (email, id);
+CREATE INDEX student_created_at_idx ON student (created_at, id);
+CREATE INDEX student_course_id_idx ON student (course_id);
@@ -0,0 +1,2 @@
+-- Bumped on every update, an edit made against an older version is refused
+ALTER TABLE course ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,25 @@
+DROP INDEX avatar_deleted_at_idx;
+DROP INDEX subject_deleted_at_idx;
+DROP INDEX student_deleted_at_idx;
+DROP INDEX course_deleted_at_idx;
+
+-- Whatever is still in the trash is purged, the tables can not tell it apart anymore
+DELETE FROM avatar
+WHERE deleted_at IS NOT NULL
+    OR student_id IN (SELECT id FROM student WHERE deleted_at IS NOT NULL);
+DELETE FROM subject_course
+WHERE subject_id IN (SELECT id FROM subject WHERE deleted_at IS NOT NULL)
+    OR course_id IN (SELECT id FROM course WHERE deleted_at IS NOT NULL);
+DELETE FROM subject WHERE deleted_at IS NOT NULL;
+DELETE FROM student WHERE deleted_at IS NOT NULL;
+DELETE FROM course
+WHERE deleted_at IS NOT NULL
+    AND NOT EXISTS (SELECT 1 FROM student s WHERE s.course_id = course.id);
+
+DROP INDEX avatar_student_id_key;
+ALTER TABLE avatar ADD CONSTRAINT avatar_student_id_key UNIQUE (student_id);
+
+ALTER TABLE avatar DROP COLUMN deleted_at;
+ALTER TABLE subject DROP COLUMN deleted_at;
+ALTER TABLE student DROP COLUMN deleted_at;
+ALTER TABLE course DROP COLUMN deleted_at;
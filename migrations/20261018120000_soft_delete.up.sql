@@ -0,0 +1,15 @@
+-- Deleted rows are kept in the trash until they are restored or purged
+ALTER TABLE course ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE student ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE subject ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE avatar ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- A trashed avatar must not keep its student from getting a new one, the index keeps the name
+-- of the constraint it replaces
+ALTER TABLE avatar DROP CONSTRAINT avatar_student_id_key;
+CREATE UNIQUE INDEX avatar_student_id_key ON avatar (student_id) WHERE deleted_at IS NULL;
+
+CREATE INDEX course_deleted_at_idx ON course (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX student_deleted_at_idx ON student (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX subject_deleted_at_idx ON subject (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX avatar_deleted_at_idx ON avatar (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,3 @@
+DROP INDEX subject_code_key;
+DROP INDEX course_name_key;
+DROP INDEX student_email_key;
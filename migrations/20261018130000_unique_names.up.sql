@@ -0,0 +1,49 @@
+-- Emails, course names and subject codes are compared without case, trashed rows do not hold
+-- their value
+
+-- Two students sharing an email cannot be told apart by renaming one of them, the operator has to
+-- pick the right address before the index can be built
+DO $$
+DECLARE
+    clashes TEXT;
+BEGIN
+    SELECT string_agg(format('%s (students %s)', email, ids), E'\n' ORDER BY email)
+    INTO clashes
+    FROM (
+        SELECT lower(email) email, string_agg(id, ', ' ORDER BY created_at, id) ids
+        FROM student
+        WHERE deleted_at IS NULL
+        GROUP BY lower(email)
+        HAVING COUNT(*) > 1
+    ) duplicates;
+
+    IF clashes IS NOT NULL THEN
+        RAISE EXCEPTION 'students share an email, which must be unique whatever its case'
+            USING DETAIL = clashes,
+                HINT = 'Change the email of all but one student of each group, or trash them, then run the migration again.';
+    END IF;
+END
+$$;
+
+-- The oldest course keeps its name, newer ones with the same name get their id appended
+UPDATE course c SET name = c.name || ' (' || c.id || ')'
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY lower(name) ORDER BY created_at, id) position
+    FROM course
+    WHERE deleted_at IS NULL
+) duplicates
+WHERE duplicates.id = c.id AND duplicates.position > 1;
+
+-- Subjects have no creation time, the lowest id keeps its code within the program
+UPDATE subject s SET code = s.code || ' (' || s.id || ')'
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY lower(code), md5(program) ORDER BY id) position
+    FROM subject
+    WHERE deleted_at IS NULL
+) duplicates
+WHERE duplicates.id = s.id AND duplicates.position > 1;
+
+CREATE UNIQUE INDEX student_email_key ON student (lower(email)) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX course_name_key ON course (lower(name)) WHERE deleted_at IS NULL;
+-- The program is hashed, a long one would not fit in an index entry
+CREATE UNIQUE INDEX subject_code_key ON subject (lower(code), md5(program)) WHERE deleted_at IS NULL;
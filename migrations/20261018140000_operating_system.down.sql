@@ -0,0 +1,14 @@
+DROP VIEW student_view;
+
+ALTER TABLE student ADD COLUMN operational_systems _text NOT NULL DEFAULT '{}';
+UPDATE student s SET operational_systems = ARRAY(
+    SELECT os.name
+    FROM student_operating_system so
+    INNER JOIN operating_system os ON os.id = so.operating_system_id
+    WHERE so.student_id = s.id
+    ORDER BY lower(os.name)
+);
+ALTER TABLE student ALTER COLUMN operational_systems DROP DEFAULT;
+
+DROP TABLE student_operating_system;
+DROP TABLE operating_system;
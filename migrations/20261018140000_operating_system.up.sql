@@ -0,0 +1,73 @@
+-- Operating systems are picked from a managed list instead of typed as free text in each student
+CREATE TABLE operating_system (
+    id VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX operating_system_name_key ON operating_system (lower(name));
+
+CREATE TABLE student_operating_system (
+    student_id VARCHAR NOT NULL,
+    operating_system_id VARCHAR NOT NULL,
+    PRIMARY KEY (student_id, operating_system_id),
+    -- Purging a student from the trash takes its operating systems along
+    FOREIGN KEY (student_id) REFERENCES student(id) ON DELETE CASCADE,
+    FOREIGN KEY (operating_system_id) REFERENCES operating_system(id)
+);
+
+CREATE INDEX student_operating_system_operating_system_id_idx
+    ON student_operating_system (operating_system_id);
+
+-- Spellings found in the arrays, mapped to the name kept in the table
+CREATE TEMPORARY TABLE operating_system_alias (
+    alias VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL
+) ON COMMIT DROP;
+
+INSERT INTO operating_system_alias (alias, name) VALUES
+    ('osx', 'macOS'),
+    ('os x', 'macOS'),
+    ('mac', 'macOS'),
+    ('mac os', 'macOS'),
+    ('macos', 'macOS'),
+    ('win', 'Windows'),
+    ('windows', 'Windows'),
+    ('linux', 'Linux'),
+    ('gnu/linux', 'Linux');
+
+CREATE TEMPORARY TABLE student_operating_system_name ON COMMIT DROP AS
+SELECT DISTINCT s.id student_id, coalesce(a.name, trim(os.name)) "name"
+FROM student s
+CROSS JOIN LATERAL unnest(s.operational_systems) os (name)
+LEFT JOIN operating_system_alias a ON a.alias = lower(trim(os.name))
+WHERE trim(os.name) <> '';
+
+-- The names the forms offered so far win over other spellings of the same name
+INSERT INTO operating_system (id, name)
+SELECT DISTINCT ON (lower(name)) substr(md5(lower(name)), 1, 10), name
+FROM (
+    SELECT name, 0 priority FROM (VALUES ('Linux'), ('macOS'), ('Windows')) offered (name)
+    UNION ALL
+    SELECT name, 1 priority FROM student_operating_system_name
+) names
+ORDER BY lower(name), priority, name;
+
+INSERT INTO student_operating_system (student_id, operating_system_id)
+SELECT DISTINCT n.student_id, os.id
+FROM student_operating_system_name n
+INNER JOIN operating_system os ON lower(os.name) = lower(n.name);
+
+ALTER TABLE student DROP COLUMN operational_systems;
+
+-- Students with the names of their operating systems, read wherever a whole student is returned
+CREATE VIEW student_view AS
+SELECT
+    s.*,
+    ARRAY(
+        SELECT os.name
+        FROM student_operating_system so
+        INNER JOIN operating_system os ON os.id = so.operating_system_id
+        WHERE so.student_id = s.id
+        ORDER BY lower(os.name)
+    ) operational_systems
+FROM student s;
@@ -0,0 +1,14 @@
+ALTER TABLE student DROP CONSTRAINT student_language_fkey;
+
+-- Students from before the catalog get their language back as it was typed, newer ones the name of
+-- their tag, or nothing when it is undetermined
+UPDATE student s SET language = coalesce(
+    s.language_legacy,
+    CASE WHEN l.code = 'und' THEN '' ELSE l.name END
+)
+FROM language l
+WHERE l.code = s.language;
+
+ALTER TABLE student DROP COLUMN language_legacy;
+
+DROP TABLE language;
@@ -0,0 +1,116 @@
+-- Languages spoken by the students, keyed by their BCP-47 tag (e.g. en, pt-BR) and picked from
+-- a catalog, students keep the tag
+CREATE TABLE language (
+    code VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL
+);
+
+-- Region tags are entries of their own. "und" is the BCP-47 tag for an undetermined language, it
+-- holds the students that had no language
+INSERT INTO language (code, name) VALUES
+    ('de', 'German'),
+    ('en', 'English'),
+    ('en-GB', 'English (United Kingdom)'),
+    ('en-US', 'English (United States)'),
+    ('es', 'Spanish'),
+    ('fr', 'French'),
+    ('it', 'Italian'),
+    ('ja', 'Japanese'),
+    ('pt', 'Portuguese'),
+    ('pt-BR', 'Portuguese (Brazil)'),
+    ('pt-PT', 'Portuguese (Portugal)'),
+    ('zh', 'Chinese'),
+    ('und', 'Undetermined');
+
+-- Spellings found in the students, mapped to the tag kept in the catalog
+CREATE TEMPORARY TABLE language_alias (
+    alias VARCHAR PRIMARY KEY,
+    code VARCHAR NOT NULL
+) ON COMMIT DROP;
+
+INSERT INTO language_alias (alias, code) VALUES
+    ('german', 'de'),
+    ('deutsch', 'de'),
+    ('alemão', 'de'),
+    ('alemao', 'de'),
+    ('english', 'en'),
+    ('inglês', 'en'),
+    ('ingles', 'en'),
+    ('spanish', 'es'),
+    ('español', 'es'),
+    ('espanol', 'es'),
+    ('espanhol', 'es'),
+    ('french', 'fr'),
+    ('français', 'fr'),
+    ('francais', 'fr'),
+    ('francês', 'fr'),
+    ('frances', 'fr'),
+    ('italian', 'it'),
+    ('italiano', 'it'),
+    ('japanese', 'ja'),
+    ('japonês', 'ja'),
+    ('japones', 'ja'),
+    ('português', 'pt'),
+    ('portugues', 'pt'),
+    ('brazilian portuguese', 'pt-BR'),
+    ('português do brasil', 'pt-BR'),
+    ('chinese', 'zh'),
+    ('chinês', 'zh'),
+    ('chines', 'zh'),
+    ('mandarin', 'zh');
+
+-- Tags are compared whatever their case and separator, so "pt_br" matches pt-BR
+CREATE TEMPORARY TABLE student_language ON COMMIT DROP AS
+SELECT
+    s.id student_id,
+    s.spelling,
+    coalesce(a.code, l.code, CASE WHEN s.spelling = '' THEN 'und' END) code
+FROM (
+    SELECT id, trim(language) spelling, lower(replace(trim(language), '_', '-')) "name"
+    FROM student
+) s
+LEFT JOIN language_alias a ON a.alias = s.name
+LEFT JOIN language l ON s.name IN (lower(l.code), lower(l.name));
+
+-- Languages outside the catalog are kept under a private use tag, one per spelling whatever its
+-- case. The tag starts with the first 8 ASCII letters and digits of the spelling, if any, and ends
+-- with a hash of it, e.g. "Klingon" becomes und-x-klingon-d3ee8e91 and "C++" does not share the
+-- tag of "C". A hash shared by two spellings gets their position appended.
+UPDATE student_language sl SET code = tags.code
+FROM (
+    SELECT
+        spelling,
+        CASE WHEN row_number() OVER (PARTITION BY code ORDER BY spelling) > 1
+            THEN code || '-' || row_number() OVER (PARTITION BY code ORDER BY spelling)
+            ELSE code
+        END code
+    FROM (
+        SELECT
+            spelling,
+            'und-x-' || coalesce(
+                nullif(left(regexp_replace(spelling, '[^a-z0-9]+', '', 'g'), 8), '') || '-',
+                ''
+            ) || left(md5(spelling), 8) code
+        FROM (
+            SELECT DISTINCT lower(spelling) spelling FROM student_language WHERE code IS NULL
+        ) spellings
+    ) hashed
+) tags
+WHERE sl.code IS NULL AND lower(sl.spelling) = tags.spelling;
+
+-- The tag is named after the first spelling of its students
+INSERT INTO language (code, name)
+SELECT DISTINCT ON (code) code, spelling
+FROM student_language
+WHERE code NOT IN (SELECT code FROM language)
+ORDER BY code, spelling;
+
+-- The language as it was typed, for the down migration to put back
+ALTER TABLE student ADD COLUMN language_legacy VARCHAR;
+
+UPDATE student s SET language_legacy = s.language, language = sl.code
+FROM student_language sl
+WHERE sl.student_id = s.id;
+
+ALTER TABLE student
+    ADD CONSTRAINT student_language_fkey FOREIGN KEY (language) REFERENCES language(code);
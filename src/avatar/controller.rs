@@ -1,57 +1,321 @@
 use axum::{
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect},
-    Form,
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
+use axum_extra::extract::{Form, WithRejection};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    custom::HtmlResponse,
-    student::{self, model::Student},
-    view::render_template,
+    custom::{HtmlResponse, JsonError},
+    error::{AppError, AppResult},
+    pagination::{Page, PageQuery},
+    state::AppState,
+    student::model::Student,
+    validation::{FieldErrors, Validate},
 };
 
-use super::{model::Avatar, service::SERVICE};
+use super::model::Avatar;
 
-#[derive(Debug, Deserialize)]
+const NAME_MAX_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateAvatarControllerModel {
+    #[serde(default)]
     name: String,
+    #[serde(default)]
     student: String,
 }
 
+/// Renames the avatar, `student` moves it to another student when given.
+#[derive(Debug, Deserialize)]
+pub struct UpdateAvatarControllerModel {
+    #[serde(default)]
+    name: String,
+    student: Option<String>,
+}
+
+impl UpdateAvatarControllerModel {
+    fn student(&self) -> Option<&str> {
+        self.student
+            .as_deref()
+            .filter(|student| !student.is_empty())
+    }
+}
+
+fn validate_name(errors: &mut FieldErrors, name: &str) {
+    errors
+        .required("name", name)
+        .max_length("name", name, NAME_MAX_LENGTH);
+}
+
+impl Validate for CreateAvatarControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validate_name(&mut errors, &self.name);
+        errors.required("student", &self.student);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateAvatarControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validate_name(&mut errors, &self.name);
+        errors.into_result()
+    }
+}
+
+/// `avatar` holds the submitted values when the form is rendered again to fix its `errors`.
+#[derive(Serialize)]
+pub struct CreateAvatarHtmlControllerModel {
+    students: Vec<Student>,
+    avatar: Option<CreateAvatarControllerModel>,
+    errors: FieldErrors,
+}
+
 #[derive(Serialize)]
 pub struct ListAvatarWithStudentControllerModel {
     avatar: Avatar,
     student: Student,
 }
 
-pub async fn list_avatar_html() -> impl IntoResponse {
-    let students = match SERVICE.list_with_students().await {
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
-        Ok(students) => students,
+pub async fn list_avatar_html(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+) -> AppResult<Response> {
+    let avatars_with_students = state
+        .avatars
+        .list_with_students(&page.try_into()?)
+        .await?
+        .map(|(avatar, student)| ListAvatarWithStudentControllerModel { avatar, student });
+
+    Ok(state
+        .views
+        .render_template("avatar/list", avatars_with_students.into())?
+        .to_html_response())
+}
+
+#[derive(Serialize)]
+pub struct EditAvatarHtmlControllerModel {
+    avatar: Avatar,
+    student: Student,
+    students: Vec<Student>,
+    errors: FieldErrors,
+}
+
+async fn find_avatar(state: &AppState, avatar_id: &str) -> AppResult<(Avatar, Student)> {
+    state
+        .avatars
+        .get_with_student(avatar_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Avatar does not exists".to_string()))
+}
+
+pub async fn show_avatar_html(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+) -> AppResult<Response> {
+    let (avatar, student) = find_avatar(&state, &avatar_id).await?;
+    let context = ListAvatarWithStudentControllerModel { avatar, student };
+
+    Ok(state
+        .views
+        .render_template("avatar/show", context.into())?
+        .to_html_response())
+}
+
+async fn render_edit_form(
+    state: &AppState,
+    avatar: Avatar,
+    student: Student,
+    errors: FieldErrors,
+) -> AppResult<String> {
+    // The avatar can stay with its student or move to one that has no avatar yet.
+    let mut students = vec![student.clone()];
+    students.extend(
+        state
+            .students
+            .list_students_that_doesnt_have_avatar()
+            .await?,
+    );
+
+    let context = EditAvatarHtmlControllerModel {
+        avatar,
+        student,
+        students,
+        errors,
     };
 
-    let avatars_with_students: Vec<ListAvatarWithStudentControllerModel> = students
-        .into_iter()
-        .map(|(avatar, student)| ListAvatarWithStudentControllerModel { avatar, student })
-        .collect();
+    state.views.render_template("avatar/edit", context.into())
+}
+
+pub async fn edit_avatar_html(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+) -> AppResult<Response> {
+    let (avatar, student) = find_avatar(&state, &avatar_id).await?;
 
-    render_template("avatar/list", avatars_with_students.into()).to_html_response()
+    Ok(
+        render_edit_form(&state, avatar, student, FieldErrors::new())
+            .await?
+            .to_html_response(),
+    )
 }
 
-pub async fn create_avatar_html() -> impl IntoResponse {
-    match student::service::SERVICE
-        .list_students_that_doesnt_have_avatar()
-        .await
-    {
-        Ok(students) => render_template("avatar/create", students.into()).to_html_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+async fn render_create_form(
+    state: &AppState,
+    avatar: Option<CreateAvatarControllerModel>,
+    errors: FieldErrors,
+) -> AppResult<String> {
+    let context = CreateAvatarHtmlControllerModel {
+        students: state
+            .students
+            .list_students_that_doesnt_have_avatar()
+            .await?,
+        avatar,
+        errors,
+    };
+
+    state.views.render_template("avatar/create", context.into())
+}
+
+pub async fn create_avatar_html(State(state): State<AppState>) -> AppResult<Response> {
+    Ok(render_create_form(&state, None, FieldErrors::new())
+        .await?
+        .to_html_response())
+}
+
+pub async fn create_avatar(
+    State(state): State<AppState>,
+    Form(payload): Form<CreateAvatarControllerModel>,
+) -> AppResult<Response> {
+    if let Err(errors) = payload.validate() {
+        let html = render_create_form(&state, Some(payload), errors).await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    // The student is checked by the service, it may have been trashed since the form loaded.
+    match state.avatars.save(&payload.name, &payload.student).await {
+        Err(AppError::InvalidFields(errors)) => {
+            let html = render_create_form(&state, Some(payload), errors).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
+        }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to("/avatars").into_response()),
     }
 }
 
-pub async fn create_avatar(Form(payload): Form<CreateAvatarControllerModel>) -> impl IntoResponse {
-    match SERVICE.save(&payload.name, &payload.student).await {
-        Ok(_) => Redirect::to("/avatars").into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+/// Edit form showing the submitted name and student next to their `errors`.
+async fn render_submitted_edit_form(
+    state: &AppState,
+    avatar_id: &str,
+    payload: &UpdateAvatarControllerModel,
+    errors: FieldErrors,
+) -> AppResult<String> {
+    let (mut avatar, student) = find_avatar(state, avatar_id).await?;
+    avatar.fantasy_name = payload.name.clone();
+    if let Some(student_id) = payload.student() {
+        avatar.student_id = student_id.to_string();
+    }
+
+    render_edit_form(state, avatar, student, errors).await
+}
+
+pub async fn edit_avatar(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+    Form(payload): Form<UpdateAvatarControllerModel>,
+) -> AppResult<Response> {
+    if let Err(errors) = payload.validate() {
+        let html = render_submitted_edit_form(&state, &avatar_id, &payload, errors).await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    let updated = state
+        .avatars
+        .update(&avatar_id, &payload.name, payload.student())
+        .await;
+
+    match updated {
+        Err(AppError::InvalidFields(errors)) => {
+            let html = render_submitted_edit_form(&state, &avatar_id, &payload, errors).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
+        }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to(&format!("/avatar/{avatar_id}")).into_response()),
     }
 }
+
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+) -> AppResult<StatusCode> {
+    state.avatars.delete(&avatar_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_avatars_json(
+    State(state): State<AppState>,
+    WithRejection(Query(page), _): WithRejection<Query<PageQuery>, JsonError>,
+) -> Result<Json<Page<ListAvatarWithStudentControllerModel>>, JsonError> {
+    let avatars = state
+        .avatars
+        .list_with_students(&page.try_into()?)
+        .await?
+        .map(|(avatar, student)| ListAvatarWithStudentControllerModel { avatar, student });
+
+    Ok(Json(avatars))
+}
+
+pub async fn show_avatar_json(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+) -> Result<Json<Avatar>, JsonError> {
+    let avatar = state
+        .avatars
+        .get_by_id(&avatar_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Avatar does not exists".to_string()))?;
+
+    Ok(Json(avatar))
+}
+
+pub async fn create_avatar_json(
+    State(state): State<AppState>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateAvatarControllerModel>, JsonError>,
+) -> Result<impl IntoResponse, JsonError> {
+    payload.validate()?;
+    let avatar = state.avatars.save(&payload.name, &payload.student).await?;
+
+    Ok((StatusCode::CREATED, Json(avatar)))
+}
+
+pub async fn update_avatar_json(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateAvatarControllerModel>, JsonError>,
+) -> Result<Json<Avatar>, JsonError> {
+    payload.validate()?;
+    let avatar = state
+        .avatars
+        .update(&avatar_id, &payload.name, payload.student())
+        .await?;
+
+    Ok(Json(avatar))
+}
+
+pub async fn delete_avatar_json(
+    State(state): State<AppState>,
+    Path(avatar_id): Path<String>,
+) -> Result<StatusCode, JsonError> {
+    state.avatars.delete(&avatar_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,59 +1,169 @@
 use super::model::Avatar;
-use crate::{infra, student::model::Student};
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{
+    error::{AppError, AppResult},
+    pagination::{unknown_cursor, Page, PageRequest},
+    student::model::Student,
+};
+use async_trait::async_trait;
 use sqlx::{Pool, Postgres};
-use std::error::Error;
+use tracing::instrument;
 
-pub struct Repository {
-    database: &'static Pool<Postgres>,
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn insert(&self, avatar: &Avatar) -> AppResult<Avatar>;
+
+    /// Fails with `NotFound` when there is no avatar with this id.
+    async fn update(&self, avatar: &Avatar) -> AppResult<Avatar>;
+
+    async fn get_by_id(&self, avatar_id: &str) -> AppResult<Option<Avatar>>;
+
+    /// Moves the avatar to the trash.
+    async fn delete(&self, avatar_id: &str) -> AppResult<()>;
+
+    /// Avatars of the students enrolled in the course.
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Avatar>>;
+
+    /// Fails with `BadRequest` when the cursor is not a live avatar.
+    async fn list_with_student(&self, page: &PageRequest) -> AppResult<Page<(Avatar, Student)>>;
 }
 
-impl Repository {
-    pub fn new() -> Self {
-        Repository {
-            database: infra::db::DB_POOL.get().expect("Unable to get DB_POOL"),
-        }
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
     }
+}
 
-    pub async fn save(&self, avatar: &Avatar) -> Result<Avatar, Box<dyn Error>> {
-        let updated_avatar = sqlx::query_as!(
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.insert"))]
+    async fn insert(&self, avatar: &Avatar) -> AppResult<Avatar> {
+        let inserted_avatar = sqlx::query_as!(
             Avatar,
             r#"
             INSERT INTO avatar (id, fantasy_name, student_id)
             VALUES ($1, $2, $3)
-            ON CONFLICT (id) DO UPDATE SET fantasy_name=excluded.fantasy_name, student_id=excluded.student_id
             RETURNING id, fantasy_name, student_id
             "#,
             avatar.get_id(),
             avatar.get_fantasy_name(),
             avatar.get_student_id()
         )
-        .fetch_one(self.database)
+        .fetch_one(&self.database)
         .await?;
 
-        Ok(updated_avatar)
+        Ok(inserted_avatar)
     }
 
-    pub async fn get_by_student_id(
-        &self,
-        student_id: &str,
-    ) -> Result<Option<Avatar>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.update"))]
+    async fn update(&self, avatar: &Avatar) -> AppResult<Avatar> {
+        let updated_avatar = sqlx::query_as!(
+            Avatar,
+            r#"
+            UPDATE avatar SET fantasy_name = $2, student_id = $3
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, fantasy_name, student_id
+            "#,
+            avatar.get_id(),
+            avatar.get_fantasy_name(),
+            avatar.get_student_id()
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        updated_avatar.ok_or_else(|| AppError::NotFound("Avatar does not exists".to_string()))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.get_by_id"))]
+    async fn get_by_id(&self, avatar_id: &str) -> AppResult<Option<Avatar>> {
         let avatar = sqlx::query_as!(
             Avatar,
             r#"
             SELECT id, fantasy_name, student_id
             FROM avatar
-            WHERE 
-                student_id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
-            student_id
+            avatar_id
         )
-        .fetch_optional(self.database)
+        .fetch_optional(&self.database)
         .await?;
 
         Ok(avatar)
     }
 
-    pub async fn list_with_student(&self) -> Result<Vec<(Avatar, Student)>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.delete"))]
+    async fn delete(&self, avatar_id: &str) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE avatar SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            avatar_id
+        )
+        .execute(&self.database)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.list_by_course_id"))]
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Avatar>> {
+        let avatars = sqlx::query_as!(
+            Avatar,
+            r#"
+            SELECT a.id, a.fantasy_name, a.student_id
+            FROM avatar a
+            INNER JOIN student s ON s.id = a.student_id
+            WHERE s.course_id = $1 AND a.deleted_at IS NULL AND s.deleted_at IS NULL
+            "#,
+            course_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(avatars)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "avatar.list_with_student"))]
+    async fn list_with_student(&self, page: &PageRequest) -> AppResult<Page<(Avatar, Student)>> {
+        if let Some(cursor) = page.anchor() {
+            let known = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM avatar WHERE id = $1 AND deleted_at IS NULL) "known!"
+                "#,
+                cursor
+            )
+            .fetch_one(&self.database)
+            .await?;
+
+            if !known {
+                return Err(unknown_cursor("avatar"));
+            }
+        }
+
+        // The previous page ends right before the row of `before`.
+        let page = &match &page.before {
+            Some(before) => {
+                let position = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) "position!" FROM avatar WHERE deleted_at IS NULL
+                        AND (fantasy_name, id) < (SELECT fantasy_name, id FROM avatar WHERE id = $1)
+                    "#,
+                    before
+                )
+                .fetch_one(&self.database)
+                .await?;
+
+                page.ending_before(position)
+            }
+            None => page.clone(),
+        };
+
         let rows = sqlx::query!(
             r#"
             select
@@ -61,13 +171,30 @@ impl Repository {
                 row_to_json(s.*) "student" 
             from
                 avatar a
-            inner join student s on
+            inner join student_view s on
                 s.id = a.student_id
-            "#
+            where
+                a.deleted_at is null
+                and (
+                    $1::text is null
+                    or (a.fantasy_name, a.id) > (select fantasy_name, id from avatar where id = $1)
+                )
+            order by
+                a.fantasy_name, a.id
+            limit $2 offset $3
+            "#,
+            page.cursor,
+            page.limit(),
+            page.offset(),
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
+        let total =
+            sqlx::query_scalar!(r#"SELECT COUNT(*) "total!" FROM avatar WHERE deleted_at IS NULL"#)
+                .fetch_one(&self.database)
+                .await?;
+
         let avatars_with_student: Vec<(Avatar, Student)> = rows
             .into_iter()
             .filter_map(|row| match (row.avatar, row.student) {
@@ -80,6 +207,124 @@ impl Repository {
             })
             .collect();
 
-        Ok(avatars_with_student)
+        Ok(Page::new(
+            avatars_with_student,
+            total,
+            page,
+            |(avatar, _)| &avatar.id,
+        ))
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn check_student(tables: &memory::Tables, avatar: &Avatar) -> AppResult<()> {
+        if !tables.students.iter().any(|s| s.id == avatar.student_id) {
+            return Err(memory::constraint_violation("avatar_student_id_fkey"));
+        }
+
+        if tables
+            .avatars
+            .iter()
+            .any(|a| a.student_id == avatar.student_id && a.id != avatar.id)
+        {
+            return Err(memory::unique_violation("avatar_student_id_key"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn insert(&self, avatar: &Avatar) -> AppResult<Avatar> {
+        let mut tables = self.database.write();
+        Self::check_student(&tables, avatar)?;
+
+        tables.avatars.push(avatar.clone());
+
+        Ok(avatar.clone())
+    }
+
+    async fn update(&self, avatar: &Avatar) -> AppResult<Avatar> {
+        let mut tables = self.database.write();
+
+        if !tables.avatars.iter().any(|a| a.id == avatar.id) {
+            return Err(AppError::NotFound("Avatar does not exists".to_string()));
+        }
+
+        Self::check_student(&tables, avatar)?;
+        memory::upsert(&mut tables.avatars, avatar.clone(), |a| a.id == avatar.id);
+
+        Ok(avatar.clone())
+    }
+
+    async fn get_by_id(&self, avatar_id: &str) -> AppResult<Option<Avatar>> {
+        let tables = self.database.read();
+
+        Ok(tables.avatars.iter().find(|a| a.id == avatar_id).cloned())
+    }
+
+    async fn delete(&self, avatar_id: &str) -> AppResult<()> {
+        let mut guard = self.database.write();
+        let tables = &mut *guard;
+
+        memory::Tables::trash(
+            &mut tables.avatars,
+            &mut tables.trash,
+            time::OffsetDateTime::now_utc(),
+            |a| a.id == avatar_id,
+            memory::TrashedRow::Avatar,
+        );
+
+        Ok(())
+    }
+
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Avatar>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .avatars
+            .iter()
+            .filter(|a| {
+                tables
+                    .students
+                    .iter()
+                    .any(|s| s.id == a.student_id && s.course_id == course_id)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn list_with_student(&self, page: &PageRequest) -> AppResult<Page<(Avatar, Student)>> {
+        let tables = self.database.read();
+
+        if let Some(cursor) = page.anchor() {
+            if !tables.avatars.iter().any(|a| a.id == *cursor) {
+                return Err(unknown_cursor("avatar"));
+            }
+        }
+
+        let mut avatars: Vec<_> = tables
+            .avatars
+            .iter()
+            .filter_map(|avatar| {
+                let student = tables.students.iter().find(|s| s.id == avatar.student_id)?;
+                Some((avatar.clone(), student.clone()))
+            })
+            .collect();
+        avatars.sort_by(|(a, _), (b, _)| (&a.fantasy_name, &a.id).cmp(&(&b.fantasy_name, &b.id)));
+
+        Ok(memory::paginate(avatars, page, |(avatar, _)| &avatar.id))
     }
 }
@@ -1,49 +1,114 @@
-use super::{model::Avatar, repository::Repository};
-use crate::student::{self, model::Student};
-use once_cell::sync::Lazy;
-use std::error::Error;
+use std::sync::Arc;
+
+use tracing::instrument;
 
-pub static SERVICE: Lazy<Service> = Lazy::new(Service::new);
+use super::{model::Avatar, repository::Repository};
+use crate::{
+    error::{AppError, AppResult},
+    pagination::{Page, PageRequest},
+    student::{model::Student, repository::Repository as StudentRepository},
+    validation::FieldErrors,
+};
 
 pub struct Service {
-    repository: Repository,
+    repository: Arc<dyn Repository>,
+    student_repository: Arc<dyn StudentRepository>,
 }
 
 impl Service {
-    pub fn new() -> Self {
+    pub fn new(
+        repository: Arc<dyn Repository>,
+        student_repository: Arc<dyn StudentRepository>,
+    ) -> Self {
         Service {
-            repository: Repository::new(),
+            repository,
+            student_repository,
         }
     }
 
-    pub async fn save(
-        &self,
-        fantasy_name: &str,
-        student_id: &str,
-    ) -> Result<Avatar, Box<dyn Error>> {
-        if let None = student::service::SERVICE
-            .get_student_by_id(student_id)
+    /// Reported on the `student` field, the student may have been trashed since the form loaded.
+    #[instrument(level = "debug", skip(self))]
+    async fn ensure_student_exists(&self, student_id: &str) -> AppResult<()> {
+        let mut errors = FieldErrors::new();
+        if self
+            .student_repository
+            .get_by_id(student_id)
             .await?
+            .is_none()
         {
-            return Err("Student does not exists".into());
+            errors.add("student", "is not an existing student");
         }
 
-        if let Some(_) = SERVICE.get_by_student_id(&student_id).await? {
-            return Err("Student already has an avatar!".into());
-        }
+        Ok(errors.into_result()?)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn save(&self, fantasy_name: &str, student_id: &str) -> AppResult<Avatar> {
+        self.ensure_student_exists(student_id).await?;
 
         let avatar = Avatar::new(fantasy_name, student_id);
-        self.repository.save(&avatar).await
+        self.repository.insert(&avatar).await
     }
 
-    pub async fn get_by_student_id(
+    /// Renames the avatar and, when `student_id` is given, moves it to that student. Moving to
+    /// a student who already has an avatar is a conflict.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn update(
         &self,
-        student_id: &str,
-    ) -> Result<Option<Avatar>, Box<dyn Error>> {
-        self.repository.get_by_student_id(student_id).await
+        avatar_id: &str,
+        fantasy_name: &str,
+        student_id: Option<&str>,
+    ) -> AppResult<Avatar> {
+        let mut avatar = match self.repository.get_by_id(avatar_id).await? {
+            None => return Err(AppError::NotFound("Avatar does not exists".to_string())),
+            Some(avatar) => avatar,
+        };
+
+        if let Some(student_id) = student_id.filter(|id| *id != avatar.student_id) {
+            self.ensure_student_exists(student_id).await?;
+            avatar.student_id = student_id.to_string();
+        }
+
+        avatar.fantasy_name = fantasy_name.to_string();
+
+        self.repository.update(&avatar).await
     }
 
-    pub async fn list_with_students(&self) -> Result<Vec<(Avatar, Student)>, Box<dyn Error>> {
-        self.repository.list_with_student().await
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete(&self, avatar_id: &str) -> AppResult<()> {
+        if self.repository.get_by_id(avatar_id).await?.is_none() {
+            return Err(AppError::NotFound("Avatar does not exists".to_string()));
+        }
+
+        self.repository.delete(avatar_id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_id(&self, avatar_id: &str) -> AppResult<Option<Avatar>> {
+        self.repository.get_by_id(avatar_id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_with_student(&self, avatar_id: &str) -> AppResult<Option<(Avatar, Student)>> {
+        let avatar = match self.repository.get_by_id(avatar_id).await? {
+            None => return Ok(None),
+            Some(avatar) => avatar,
+        };
+
+        let student = self
+            .student_repository
+            .get_by_id(&avatar.student_id)
+            .await?
+            .ok_or_else(|| AppError::Internal("Avatar without student".to_string()))?;
+
+        Ok(Some((avatar, student)))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list_with_students(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Avatar, Student)>> {
+        self.repository.list_with_student(page).await
     }
 }
@@ -0,0 +1,38 @@
+use clap::{Parser, Subcommand};
+
+use crate::config::ConfigArgs;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Student, course, subject and avatar management")]
+pub struct Cli {
+    /// Apply pending migrations before the HTTP server starts
+    #[arg(long)]
+    pub migrate_on_start: bool,
+
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the HTTP server (default)
+    Serve,
+    /// Manage the embedded database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateAction {
+    /// Apply every pending migration
+    Up,
+    /// Revert the most recently applied migration
+    Down,
+    /// List the embedded migrations and whether they were applied
+    Status,
+}
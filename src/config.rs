@@ -0,0 +1,521 @@
+use std::{fmt::Display, fs, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
+
+use clap::Args;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use sqlx::postgres::PgSslMode;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+const LOG_FORMATS: [&str; 2] = ["text", "json"];
+/// A hundred years, longer periods are as good as never purging and would overflow the cutoff.
+const MAX_TRASH_RETENTION_DAYS: u64 = 36_500;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub listen_addr: SocketAddr,
+    pub database: DatabaseConfig,
+    pub template_dir: PathBuf,
+    pub assets_dir: PathBuf,
+    pub log_level: String,
+    pub log_format: String,
+    pub shutdown_timeout: Duration,
+    pub trash_retention: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub idle_timeout: Duration,
+    pub acquire_timeout: Duration,
+    /// Overrides the `sslmode` of the URL, `None` keeps the one of the URL.
+    pub ssl_mode: Option<PgSslMode>,
+    pub connect_attempts: u32,
+    pub connect_backoff: Duration,
+    pub connect_backoff_max: Duration,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    File(PathBuf, String),
+    Invalid { key: String, message: String },
+}
+
+impl ConfigError {
+    fn invalid(key: &str, message: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::File(path, message) => {
+                write!(f, "unable to read {}: {message}", path.display())
+            }
+            ConfigError::Invalid { key, message } => write!(f, "invalid `{key}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Command line flags, they take precedence over every other configuration source.
+#[derive(Args, Debug, Default)]
+pub struct ConfigArgs {
+    /// Path of the TOML configuration file [env: APP_CONFIG] [default: config.toml]
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Address the HTTP server listens on [env: LISTEN_ADDR]
+    #[arg(long, global = true)]
+    pub listen_addr: Option<String>,
+
+    /// Postgres connection string [env: DATABASE_URL]
+    #[arg(long, global = true)]
+    pub database_url: Option<String>,
+
+    /// Minimum number of pooled connections [env: DATABASE_MIN_CONNECTIONS]
+    #[arg(long, global = true)]
+    pub db_min_connections: Option<u32>,
+
+    /// Maximum number of pooled connections [env: DATABASE_MAX_CONNECTIONS]
+    #[arg(long, global = true)]
+    pub db_max_connections: Option<u32>,
+
+    /// Seconds an idle connection is kept in the pool [env: DATABASE_IDLE_TIMEOUT_SECS]
+    #[arg(long, global = true)]
+    pub db_idle_timeout_secs: Option<u64>,
+
+    /// Seconds to wait for a free connection [env: DATABASE_ACQUIRE_TIMEOUT_SECS]
+    #[arg(long, global = true)]
+    pub db_acquire_timeout_secs: Option<u64>,
+
+    /// disable, allow, prefer, require, verify-ca or verify-full, overrides the `sslmode` of the
+    /// URL [env: DATABASE_SSL_MODE]
+    #[arg(long, global = true)]
+    pub db_ssl_mode: Option<String>,
+
+    /// Times the database is tried at startup before giving up [env: DATABASE_CONNECT_ATTEMPTS]
+    #[arg(long, global = true)]
+    pub db_connect_attempts: Option<u32>,
+
+    /// Seconds before the first retry, doubled after each failure [env: DATABASE_CONNECT_BACKOFF_SECS]
+    #[arg(long, global = true)]
+    pub db_connect_backoff_secs: Option<u64>,
+
+    /// Upper bound of the delay between retries [env: DATABASE_CONNECT_BACKOFF_MAX_SECS]
+    #[arg(long, global = true)]
+    pub db_connect_backoff_max_secs: Option<u64>,
+
+    /// Directory the `.jinja` templates are hot-reloaded from in debug builds [env: TEMPLATE_DIR]
+    #[arg(long, global = true)]
+    pub template_dir: Option<String>,
+
+    /// Directory the CSS and JavaScript served under /assets live in [env: ASSETS_DIR]
+    #[arg(long, global = true)]
+    pub assets_dir: Option<String>,
+
+    /// trace, debug, info, warn or error [env: LOG_LEVEL]
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// text or json [env: LOG_FORMAT]
+    #[arg(long, global = true)]
+    pub log_format: Option<String>,
+
+    /// Seconds in-flight requests get to finish after SIGINT or SIGTERM [env: SHUTDOWN_TIMEOUT_SECS]
+    #[arg(long, global = true)]
+    pub shutdown_timeout_secs: Option<u64>,
+
+    /// Days deleted records stay in the trash before they are purged [env: TRASH_RETENTION_DAYS]
+    #[arg(long, global = true)]
+    pub trash_retention_days: Option<u64>,
+}
+
+/// One source of configuration, every unset field falls back to the previous layer.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    listen_addr: Option<String>,
+    template_dir: Option<String>,
+    assets_dir: Option<String>,
+    log_level: Option<String>,
+    log_format: Option<String>,
+    shutdown_timeout_secs: Option<u64>,
+    trash_retention_days: Option<u64>,
+    #[serde(default)]
+    database: DatabaseLayer,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatabaseLayer {
+    url: Option<String>,
+    min_connections: Option<u32>,
+    max_connections: Option<u32>,
+    idle_timeout_secs: Option<u64>,
+    acquire_timeout_secs: Option<u64>,
+    ssl_mode: Option<String>,
+    connect_attempts: Option<u32>,
+    connect_backoff_secs: Option<u64>,
+    connect_backoff_max_secs: Option<u64>,
+}
+
+impl Layer {
+    fn defaults() -> Self {
+        Layer {
+            listen_addr: Some("0.0.0.0:3000".to_string()),
+            template_dir: Some("src/view".to_string()),
+            assets_dir: Some("assets".to_string()),
+            log_level: Some("info".to_string()),
+            log_format: Some("text".to_string()),
+            shutdown_timeout_secs: Some(30),
+            trash_retention_days: Some(30),
+            database: DatabaseLayer {
+                url: None,
+                min_connections: Some(0),
+                max_connections: Some(5),
+                idle_timeout_secs: Some(600),
+                acquire_timeout_secs: Some(30),
+                ssl_mode: None,
+                connect_attempts: Some(10),
+                connect_backoff_secs: Some(1),
+                connect_backoff_max_secs: Some(30),
+            },
+        }
+    }
+
+    fn from_file(path: &PathBuf) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| ConfigError::File(path.clone(), err.to_string()))?;
+
+        toml::from_str(&content).map_err(|err| ConfigError::File(path.clone(), err.to_string()))
+    }
+
+    fn from_env() -> Result<Self, ConfigError> {
+        Ok(Layer {
+            listen_addr: env("LISTEN_ADDR"),
+            template_dir: env("TEMPLATE_DIR"),
+            assets_dir: env("ASSETS_DIR"),
+            log_level: env("LOG_LEVEL"),
+            log_format: env("LOG_FORMAT"),
+            shutdown_timeout_secs: env_parse("SHUTDOWN_TIMEOUT_SECS")?,
+            trash_retention_days: env_parse("TRASH_RETENTION_DAYS")?,
+            database: DatabaseLayer {
+                url: env("DATABASE_URL").or_else(postgres_url_from_env),
+                min_connections: env_parse("DATABASE_MIN_CONNECTIONS")?,
+                max_connections: env_parse("DATABASE_MAX_CONNECTIONS")?,
+                idle_timeout_secs: env_parse("DATABASE_IDLE_TIMEOUT_SECS")?,
+                acquire_timeout_secs: env_parse("DATABASE_ACQUIRE_TIMEOUT_SECS")?,
+                ssl_mode: env("DATABASE_SSL_MODE"),
+                connect_attempts: env_parse("DATABASE_CONNECT_ATTEMPTS")?,
+                connect_backoff_secs: env_parse("DATABASE_CONNECT_BACKOFF_SECS")?,
+                connect_backoff_max_secs: env_parse("DATABASE_CONNECT_BACKOFF_MAX_SECS")?,
+            },
+        })
+    }
+
+    fn from_args(args: &ConfigArgs) -> Self {
+        Layer {
+            listen_addr: args.listen_addr.clone(),
+            template_dir: args.template_dir.clone(),
+            assets_dir: args.assets_dir.clone(),
+            log_level: args.log_level.clone(),
+            log_format: args.log_format.clone(),
+            shutdown_timeout_secs: args.shutdown_timeout_secs,
+            trash_retention_days: args.trash_retention_days,
+            database: DatabaseLayer {
+                url: args.database_url.clone(),
+                min_connections: args.db_min_connections,
+                max_connections: args.db_max_connections,
+                idle_timeout_secs: args.db_idle_timeout_secs,
+                acquire_timeout_secs: args.db_acquire_timeout_secs,
+                ssl_mode: args.db_ssl_mode.clone(),
+                connect_attempts: args.db_connect_attempts,
+                connect_backoff_secs: args.db_connect_backoff_secs,
+                connect_backoff_max_secs: args.db_connect_backoff_max_secs,
+            },
+        }
+    }
+
+    fn merge(self, over: Layer) -> Self {
+        Layer {
+            listen_addr: over.listen_addr.or(self.listen_addr),
+            template_dir: over.template_dir.or(self.template_dir),
+            assets_dir: over.assets_dir.or(self.assets_dir),
+            log_level: over.log_level.or(self.log_level),
+            log_format: over.log_format.or(self.log_format),
+            shutdown_timeout_secs: over.shutdown_timeout_secs.or(self.shutdown_timeout_secs),
+            trash_retention_days: over.trash_retention_days.or(self.trash_retention_days),
+            database: DatabaseLayer {
+                url: over.database.url.or(self.database.url),
+                min_connections: over
+                    .database
+                    .min_connections
+                    .or(self.database.min_connections),
+                max_connections: over
+                    .database
+                    .max_connections
+                    .or(self.database.max_connections),
+                idle_timeout_secs: over
+                    .database
+                    .idle_timeout_secs
+                    .or(self.database.idle_timeout_secs),
+                acquire_timeout_secs: over
+                    .database
+                    .acquire_timeout_secs
+                    .or(self.database.acquire_timeout_secs),
+                ssl_mode: over.database.ssl_mode.or(self.database.ssl_mode),
+                connect_attempts: over
+                    .database
+                    .connect_attempts
+                    .or(self.database.connect_attempts),
+                connect_backoff_secs: over
+                    .database
+                    .connect_backoff_secs
+                    .or(self.database.connect_backoff_secs),
+                connect_backoff_max_secs: over
+                    .database
+                    .connect_backoff_max_secs
+                    .or(self.database.connect_backoff_max_secs),
+            },
+        }
+    }
+}
+
+fn env(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn env_parse<T: FromStr>(key: &str) -> Result<Option<T>, ConfigError>
+where
+    T::Err: Display,
+{
+    env(key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| ConfigError::invalid(key, format!("{err}")))
+        })
+        .transpose()
+}
+
+/// Keeps the `POSTGRES_*` variables used by docker-compose working when `DATABASE_URL` is unset.
+/// Both `POSTGRES_DB` and `POSTGRES_DATABASE` are accepted for the database name.
+fn postgres_url_from_env() -> Option<String> {
+    let user = env("POSTGRES_USER")?;
+    let password = env("POSTGRES_PASSWORD").unwrap_or_default();
+    let host = env("POSTGRES_HOST").unwrap_or_else(|| "localhost".to_string());
+    let port = env("POSTGRES_PORT").unwrap_or_else(|| "5432".to_string());
+    let database = env("POSTGRES_DB").or_else(|| env("POSTGRES_DATABASE"))?;
+
+    Some(postgres_url(&user, &password, &host, &port, &database))
+}
+
+/// Percent-encodes the parts that may hold any character. The database is passed as `dbname`
+/// since the path of the URL is not decoded by sqlx.
+pub(crate) fn postgres_url(
+    user: &str,
+    password: &str,
+    host: &str,
+    port: &str,
+    database: &str,
+) -> String {
+    let encode = |part| utf8_percent_encode(part, NON_ALPHANUMERIC);
+
+    format!(
+        "postgres://{}:{}@{host}:{port}?dbname={}",
+        encode(user),
+        encode(password),
+        encode(database)
+    )
+}
+
+impl Config {
+    /// Builds the configuration from, in increasing precedence: defaults, the TOML file,
+    /// environment variables and command line flags.
+    pub fn load(args: &ConfigArgs) -> Result<Self, ConfigError> {
+        let file = match args
+            .config
+            .clone()
+            .or_else(|| env("APP_CONFIG").map(PathBuf::from))
+        {
+            Some(path) => Layer::from_file(&path)?,
+            None => {
+                let path = PathBuf::from(DEFAULT_CONFIG_FILE);
+                match path.exists() {
+                    true => Layer::from_file(&path)?,
+                    false => Layer::default(),
+                }
+            }
+        };
+
+        let layer = Layer::defaults()
+            .merge(file)
+            .merge(Layer::from_env()?)
+            .merge(Layer::from_args(args));
+
+        Config::validate(layer)
+    }
+
+    fn validate(layer: Layer) -> Result<Self, ConfigError> {
+        let listen_addr = layer.listen_addr.unwrap_or_default();
+        let listen_addr: SocketAddr = listen_addr.parse().map_err(|_| {
+            ConfigError::invalid(
+                "listen_addr",
+                format!("`{listen_addr}` is not a socket address like 0.0.0.0:3000"),
+            )
+        })?;
+
+        let url = layer.database.url.ok_or_else(|| {
+            ConfigError::invalid(
+                "database.url",
+                "set DATABASE_URL, the POSTGRES_* variables or `url` in the [database] section",
+            )
+        })?;
+        if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+            return Err(ConfigError::invalid(
+                "database.url",
+                "must start with postgres:// or postgresql://",
+            ));
+        }
+
+        let min_connections = layer.database.min_connections.unwrap_or_default();
+        let max_connections = layer.database.max_connections.unwrap_or_default();
+        if max_connections == 0 {
+            return Err(ConfigError::invalid(
+                "database.max_connections",
+                "must be greater than zero",
+            ));
+        }
+        if min_connections > max_connections {
+            return Err(ConfigError::invalid(
+                "database.min_connections",
+                format!("{min_connections} is greater than max_connections ({max_connections})"),
+            ));
+        }
+
+        let ssl_mode = layer
+            .database
+            .ssl_mode
+            .map(|ssl_mode| {
+                PgSslMode::from_str(&ssl_mode).map_err(|_| {
+                    ConfigError::invalid(
+                        "database.ssl_mode",
+                        format!(
+                            "`{ssl_mode}` is not one of disable, allow, prefer, require, verify-ca, verify-full"
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+
+        // No connection could ever be acquired without waiting for it.
+        let acquire_timeout = layer.database.acquire_timeout_secs.unwrap_or_default();
+        if acquire_timeout == 0 {
+            return Err(ConfigError::invalid(
+                "database.acquire_timeout_secs",
+                "must be greater than zero",
+            ));
+        }
+
+        let connect_attempts = layer.database.connect_attempts.unwrap_or_default();
+        if connect_attempts == 0 {
+            return Err(ConfigError::invalid(
+                "database.connect_attempts",
+                "must be greater than zero",
+            ));
+        }
+
+        let connect_backoff =
+            Duration::from_secs(layer.database.connect_backoff_secs.unwrap_or_default());
+        let connect_backoff_max =
+            Duration::from_secs(layer.database.connect_backoff_max_secs.unwrap_or_default());
+        if connect_backoff > connect_backoff_max {
+            return Err(ConfigError::invalid(
+                "database.connect_backoff_secs",
+                format!(
+                    "{} is greater than connect_backoff_max_secs ({})",
+                    connect_backoff.as_secs(),
+                    connect_backoff_max.as_secs()
+                ),
+            ));
+        }
+
+        // Release builds embed the templates, the directory only has to exist in debug builds.
+        let template_dir = PathBuf::from(layer.template_dir.unwrap_or_default());
+        if cfg!(debug_assertions) && !template_dir.is_dir() {
+            return Err(ConfigError::invalid(
+                "template_dir",
+                format!("{} is not a directory", template_dir.display()),
+            ));
+        }
+
+        let assets_dir = PathBuf::from(layer.assets_dir.unwrap_or_default());
+        if !assets_dir.is_dir() {
+            return Err(ConfigError::invalid(
+                "assets_dir",
+                format!("{} is not a directory", assets_dir.display()),
+            ));
+        }
+
+        let log_level = layer.log_level.unwrap_or_default().to_lowercase();
+        if !LOG_LEVELS.contains(&log_level.as_str()) {
+            return Err(ConfigError::invalid(
+                "log_level",
+                format!("`{log_level}` is not one of {}", LOG_LEVELS.join(", ")),
+            ));
+        }
+
+        let log_format = layer.log_format.unwrap_or_default().to_lowercase();
+        if !LOG_FORMATS.contains(&log_format.as_str()) {
+            return Err(ConfigError::invalid(
+                "log_format",
+                format!("`{log_format}` is not one of {}", LOG_FORMATS.join(", ")),
+            ));
+        }
+
+        let trash_retention_days = layer.trash_retention_days.unwrap_or_default();
+        if trash_retention_days == 0 {
+            return Err(ConfigError::invalid(
+                "trash_retention_days",
+                "must be greater than zero",
+            ));
+        }
+        if trash_retention_days > MAX_TRASH_RETENTION_DAYS {
+            return Err(ConfigError::invalid(
+                "trash_retention_days",
+                format!("must be at most {MAX_TRASH_RETENTION_DAYS}"),
+            ));
+        }
+
+        Ok(Config {
+            listen_addr,
+            database: DatabaseConfig {
+                url,
+                min_connections,
+                max_connections,
+                idle_timeout: Duration::from_secs(
+                    layer.database.idle_timeout_secs.unwrap_or_default(),
+                ),
+                acquire_timeout: Duration::from_secs(acquire_timeout),
+                ssl_mode,
+                connect_attempts,
+                connect_backoff,
+                connect_backoff_max,
+            },
+            template_dir,
+            assets_dir,
+            log_level,
+            log_format,
+            shutdown_timeout: Duration::from_secs(layer.shutdown_timeout_secs.unwrap_or_default()),
+            trash_retention: Duration::from_secs(trash_retention_days * 24 * 60 * 60),
+        })
+    }
+}
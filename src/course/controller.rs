@@ -1,68 +1,316 @@
-use super::service::SERVICE;
-use crate::{custom::HtmlResponse, view::render_template};
+use super::model::{Course, CourseOverview};
+use crate::{
+    avatar::model::Avatar,
+    custom::{HtmlResponse, JsonError},
+    error::{AppError, AppResult},
+    pagination::{Page, PageQuery},
+    state::AppState,
+    student::model::Student,
+    subject::model::Subject,
+    validation::{FieldErrors, TakenBy, Validate},
+};
 use axum::{
-    extract::Path,
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
-use axum_extra::extract::Form;
-use serde::Deserialize;
+use axum_extra::extract::{Form, WithRejection};
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug, Clone)]
+const NAME_MAX_LENGTH: usize = 100;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CreateCourseControllerModel {
+    #[serde(default)]
     name: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct EditCourseControllerModel {
+    #[serde(default)]
     name: String,
+    /// Versions start at 1, a form or payload that leaves it out is refused.
+    #[serde(default)]
+    version: i32,
 }
 
-pub async fn course_list_html() -> impl IntoResponse {
-    match SERVICE.list_courses().await {
-        Ok(courses) => render_template("course/list", courses.into()).to_html_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+fn validate_name(errors: &mut FieldErrors, name: &str) {
+    errors
+        .required("name", name)
+        .max_length("name", name, NAME_MAX_LENGTH);
 }
 
-pub async fn create_course_form_html() -> impl IntoResponse {
-    render_template("course/create", ().into()).to_html_response()
+impl Validate for CreateCourseControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validate_name(&mut errors, &self.name);
+        errors.into_result()
+    }
 }
 
-pub async fn edit_course_form_html(Path(course_id): Path<String>) -> impl IntoResponse {
-    match SERVICE.get_course_by_id(&course_id).await {
-        Ok(course) => render_template("course/edit", course).to_html_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+impl Validate for EditCourseControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validate_name(&mut errors, &self.name);
+        if self.version < 1 {
+            errors.add("version", "is required");
+        }
+        errors.into_result()
     }
 }
 
-pub async fn show_course_html(Path(course_id): Path<String>) -> impl IntoResponse {
-    match SERVICE.get_course_by_id(&course_id).await {
-        Ok(course) => render_template("course/show", course).to_html_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+/// `course` holds the submitted values when the form is rendered again to fix its `errors`.
+#[derive(Serialize, Default)]
+pub struct CreateCourseHtmlControllerModel {
+    course: Option<CreateCourseControllerModel>,
+    errors: FieldErrors,
+    name_taken_by: Option<TakenBy>,
+}
+
+#[derive(Serialize)]
+pub struct EditCourseHtmlControllerModel {
+    course: Course,
+    errors: FieldErrors,
+    name_taken_by: Option<TakenBy>,
+}
+
+/// Course other than `course_id` that already uses the name.
+async fn name_taken_by(
+    state: &AppState,
+    name: &str,
+    course_id: Option<&str>,
+) -> AppResult<Option<TakenBy>> {
+    Ok(state
+        .courses
+        .get_by_name(name)
+        .await?
+        .filter(|owner| Some(owner.id.as_str()) != course_id)
+        .map(|owner| TakenBy {
+            entity: "course",
+            id: owner.id,
+            label: owner.name,
+        }))
+}
+
+#[derive(Serialize)]
+pub struct EnrolledStudentControllerModel {
+    student: Student,
+    avatar: Option<Avatar>,
+}
+
+#[derive(Serialize)]
+pub struct ShowCourseHtmlControllerModel {
+    course: Course,
+    students: Vec<EnrolledStudentControllerModel>,
+    subjects: Vec<Subject>,
+    students_count: usize,
+    subjects_count: usize,
+    avatars_count: usize,
+    /// Percentage of the enrolled students that have an avatar.
+    avatar_coverage: usize,
+}
+
+impl From<CourseOverview> for ShowCourseHtmlControllerModel {
+    fn from(overview: CourseOverview) -> Self {
+        let students_count = overview.students.len();
+        let avatars_count = overview
+            .students
+            .iter()
+            .filter(|(_, avatar)| avatar.is_some())
+            .count();
+
+        ShowCourseHtmlControllerModel {
+            course: overview.course,
+            students: overview
+                .students
+                .into_iter()
+                .map(|(student, avatar)| EnrolledStudentControllerModel { student, avatar })
+                .collect(),
+            subjects_count: overview.subjects.len(),
+            subjects: overview.subjects,
+            students_count,
+            avatars_count,
+            avatar_coverage: match students_count {
+                0 => 0,
+                _ => avatars_count * 100 / students_count,
+            },
+        }
     }
 }
 
-pub async fn create_course(Form(course): Form<CreateCourseControllerModel>) -> impl IntoResponse {
-    match SERVICE.save(&course.name).await {
-        Ok(_) => Redirect::to("/courses").into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+pub async fn course_list_html(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+) -> AppResult<Response> {
+    let courses = state.courses.list_courses(&page.try_into()?).await?;
+
+    Ok(state
+        .views
+        .render_template("course/list", courses.into())?
+        .to_html_response())
+}
+
+pub async fn create_course_form_html(State(state): State<AppState>) -> AppResult<Response> {
+    let context = CreateCourseHtmlControllerModel::default();
+
+    Ok(state
+        .views
+        .render_template("course/create", context.into())?
+        .to_html_response())
+}
+
+async fn find_course(state: &AppState, course_id: &str) -> AppResult<Course> {
+    state
+        .courses
+        .get_course_by_id(course_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Course does not exists".to_string()))
+}
+
+pub async fn edit_course_form_html(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+) -> AppResult<Response> {
+    let context = EditCourseHtmlControllerModel {
+        course: find_course(&state, &course_id).await?,
+        errors: FieldErrors::new(),
+        name_taken_by: None,
+    };
+
+    Ok(state
+        .views
+        .render_template("course/edit", context.into())?
+        .to_html_response())
+}
+
+pub async fn show_course_html(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+) -> AppResult<Response> {
+    let overview = state
+        .courses
+        .get_overview(&course_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Course does not exists".to_string()))?;
+    let context = ShowCourseHtmlControllerModel::from(overview);
+
+    Ok(state
+        .views
+        .render_template("course/show", context.into())?
+        .to_html_response())
+}
+
+pub async fn create_course(
+    State(state): State<AppState>,
+    Form(course): Form<CreateCourseControllerModel>,
+) -> AppResult<Response> {
+    let invalid = course.validate().err();
+    let taken_by = name_taken_by(&state, &course.name, None).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        let context = CreateCourseHtmlControllerModel {
+            course: Some(course),
+            errors: invalid.unwrap_or_default(),
+            name_taken_by: taken_by,
+        };
+        let html = state
+            .views
+            .render_template("course/create", context.into())?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
     }
+
+    state.courses.save(&course.name).await?;
+
+    Ok(Redirect::to("/courses").into_response())
 }
 
 pub async fn edit_course(
+    State(state): State<AppState>,
     Path(course_id): Path<String>,
     Form(course): Form<EditCourseControllerModel>,
-) -> impl IntoResponse {
-    match SERVICE.edit(&course_id, &course.name).await {
-        Ok(_) => Redirect::to("/courses").into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+) -> AppResult<Response> {
+    let invalid = course.validate().err();
+    let taken_by = name_taken_by(&state, &course.name, Some(&course_id)).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        // The submitted version is kept, saving the fixed form still detects a concurrent edit. A
+        // form without one gets the current version and asks to check the course first.
+        let mut submitted = find_course(&state, &course_id).await?;
+        submitted.name = course.name;
+        if course.version > 0 {
+            submitted.version = course.version;
+        }
+
+        let context = EditCourseHtmlControllerModel {
+            course: submitted,
+            errors: invalid.unwrap_or_default(),
+            name_taken_by: taken_by,
+        };
+        let html = state.views.render_template("course/edit", context.into())?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
     }
+
+    state
+        .courses
+        .edit(&course_id, &course.name, course.version)
+        .await?;
+
+    Ok(Redirect::to("/courses").into_response())
 }
 
-pub async fn delete_course(Path(course_id): Path<String>) -> impl IntoResponse {
-    match SERVICE.delete(course_id).await {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
-    }
+pub async fn delete_course(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+) -> AppResult<StatusCode> {
+    state.courses.delete(course_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_courses_json(
+    State(state): State<AppState>,
+    WithRejection(Query(page), _): WithRejection<Query<PageQuery>, JsonError>,
+) -> Result<Json<Page<Course>>, JsonError> {
+    Ok(Json(state.courses.list_courses(&page.try_into()?).await?))
+}
+
+pub async fn show_course_json(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+) -> Result<Json<Course>, JsonError> {
+    Ok(Json(find_course(&state, &course_id).await?))
+}
+
+pub async fn create_course_json(
+    State(state): State<AppState>,
+    WithRejection(Json(course), _): WithRejection<Json<CreateCourseControllerModel>, JsonError>,
+) -> Result<impl IntoResponse, JsonError> {
+    course.validate()?;
+    let course = state.courses.save(&course.name).await?;
+
+    Ok((StatusCode::CREATED, Json(course)))
+}
+
+pub async fn update_course_json(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+    WithRejection(Json(course), _): WithRejection<Json<EditCourseControllerModel>, JsonError>,
+) -> Result<Json<Course>, JsonError> {
+    course.validate()?;
+    let course = state
+        .courses
+        .edit(&course_id, &course.name, course.version)
+        .await?;
+
+    Ok(Json(course))
+}
+
+pub async fn delete_course_json(
+    State(state): State<AppState>,
+    Path(course_id): Path<String>,
+) -> Result<StatusCode, JsonError> {
+    state.courses.delete(course_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -1,4 +1,4 @@
 pub mod controller;
 pub mod model;
-mod repository;
+pub mod repository;
 pub mod service;
@@ -1,10 +1,14 @@
 use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
 
+use crate::{avatar::model::Avatar, student::model::Student, subject::model::Subject};
+
 #[derive(Clone, Serialize, Debug, Deserialize)]
 pub struct Course {
     pub id: String,
     pub name: String,
+    /// Incremented on every update, edits must be made against the current version.
+    pub version: i32,
 }
 
 impl Course {
@@ -12,13 +16,7 @@ impl Course {
         Self {
             id: Self::generate_id(),
             name: name.to_string(),
-        }
-    }
-
-    pub fn new_with_id(id: &str, name: &str) -> Self {
-        Self {
-            id: id.to_string(),
-            name: name.to_string(),
+            version: 1,
         }
     }
 
@@ -40,3 +38,11 @@ impl Course {
         &self.name
     }
 }
+
+/// A course with its enrolled students, each with the avatar it may have, and its curriculum.
+#[derive(Serialize)]
+pub struct CourseOverview {
+    pub course: Course,
+    pub students: Vec<(Student, Option<Avatar>)>,
+    pub subjects: Vec<Subject>,
+}
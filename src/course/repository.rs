@@ -1,72 +1,326 @@
-use crate::{course::model::Course, infra};
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{
+    course::model::Course,
+    error::AppResult,
+    pagination::{unknown_cursor, Page, PageRequest},
+};
+use async_trait::async_trait;
 use sqlx::{Pool, Postgres};
-use std::error::Error;
+use tracing::instrument;
 
-pub struct Repository {
-    database: &'static Pool<Postgres>,
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// Moves the course to the trash in the same statement that checks no live student is
+    /// enrolled in it, `false` when it was left in place.
+    async fn delete(&self, course_id: &str) -> AppResult<bool>;
+
+    async fn get_by_id(&self, course_id: &str) -> AppResult<Option<Course>>;
+
+    /// The live course with this name, whatever its case.
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<Course>>;
+
+    /// Fails with `BadRequest` when the cursor is not a live course.
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<Course>>;
+
+    /// Every course, for the select boxes of the student and subject forms.
+    async fn list_all(&self) -> AppResult<Vec<Course>>;
+
+    async fn insert(&self, course: &Course) -> AppResult<Course>;
+
+    /// Renames the course if it is still at `version`, `None` when the course does not exist or
+    /// was changed in the meantime.
+    async fn update(&self, course_id: &str, name: &str, version: i32) -> AppResult<Option<Course>>;
 }
 
-impl Repository {
-    pub fn new() -> Self {
-        Repository {
-            database: infra::db::DB_POOL.get().expect("Unable to get DB_POOL"),
-        }
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
     }
+}
 
-    pub async fn delete(&self, course_id: &str) -> Result<(), Box<dyn Error>> {
-        sqlx::query!(
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "course.delete"))]
+    async fn delete(&self, course_id: &str) -> AppResult<bool> {
+        let deleted = sqlx::query!(
             r#"
-            DELETE FROM course WHERE id = $1
+            UPDATE course SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM student WHERE course_id = $1 AND deleted_at IS NULL
+                )
             "#,
             course_id
         )
-        .execute(self.database)
+        .execute(&self.database)
         .await?;
 
-        Ok(())
+        Ok(deleted.rows_affected() > 0)
     }
 
-    pub async fn get_by_id(&self, course_id: &str) -> Result<Option<Course>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "course.get_by_id"))]
+    async fn get_by_id(&self, course_id: &str) -> AppResult<Option<Course>> {
         let course = sqlx::query_as!(
             Course,
             r#"
-                SELECT id, name FROM course WHERE id = $1
+                SELECT id, name, version FROM course WHERE id = $1 AND deleted_at IS NULL
             "#,
             course_id
         )
-        .fetch_optional(self.database)
+        .fetch_optional(&self.database)
         .await?;
 
         Ok(course)
     }
 
-    pub async fn list(&self) -> Result<Vec<Course>, Box<dyn Error>> {
-        let subjects = sqlx::query_as!(
+    #[instrument(level = "debug", skip_all, fields(query = "course.get_by_name"))]
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<Course>> {
+        let course = sqlx::query_as!(
+            Course,
+            r#"
+                SELECT id, name, version FROM course
+                WHERE lower(name) = lower($1) AND deleted_at IS NULL
+            "#,
+            name
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(course)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "course.list"))]
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<Course>> {
+        if let Some(cursor) = page.anchor() {
+            let known = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM course WHERE id = $1 AND deleted_at IS NULL) "known!"
+                "#,
+                cursor
+            )
+            .fetch_one(&self.database)
+            .await?;
+
+            if !known {
+                return Err(unknown_cursor("course"));
+            }
+        }
+
+        // The previous page ends right before the row of `before`.
+        let page = &match &page.before {
+            Some(before) => {
+                let position = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) "position!" FROM course WHERE deleted_at IS NULL
+                        AND (name, id) < (SELECT name, id FROM course WHERE id = $1)
+                    "#,
+                    before
+                )
+                .fetch_one(&self.database)
+                .await?;
+
+                page.ending_before(position)
+            }
+            None => page.clone(),
+        };
+
+        let courses = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT id, name, version FROM course
+            WHERE deleted_at IS NULL
+                AND ($1::text IS NULL OR (name, id) > (SELECT name, id FROM course WHERE id = $1))
+            ORDER BY name, id
+            LIMIT $2 OFFSET $3
+            "#,
+            page.cursor,
+            page.limit(),
+            page.offset(),
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let total =
+            sqlx::query_scalar!(r#"SELECT COUNT(*) "total!" FROM course WHERE deleted_at IS NULL"#)
+                .fetch_one(&self.database)
+                .await?;
+
+        Ok(Page::new(courses, total, page, |course| &course.id))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "course.list_all"))]
+    async fn list_all(&self) -> AppResult<Vec<Course>> {
+        let courses = sqlx::query_as!(
             Course,
             r#"
-            SELECT id, name FROM course
+            SELECT id, name, version FROM course WHERE deleted_at IS NULL ORDER BY name, id
             "#,
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
-        Ok(subjects)
+        Ok(courses)
     }
 
-    pub async fn save(&self, course: &Course) -> Result<Course, Box<dyn Error>> {
-        sqlx::query!(
+    #[instrument(level = "debug", skip_all, fields(query = "course.insert"))]
+    async fn insert(&self, course: &Course) -> AppResult<Course> {
+        let inserted_course = sqlx::query_as!(
+            Course,
             r#"
-            INSERT INTO course (id, name)
-            VALUES ($1, $2)
-            ON CONFLICT (id) DO UPDATE SET name=excluded.name
+            INSERT INTO course (id, name, version)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, version
             "#,
             course.get_id(),
             course.get_name(),
+            course.version,
         )
-        .execute(self.database)
-        .await
-        .expect("Error when trying to add a subject");
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(inserted_course)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "course.update"))]
+    async fn update(&self, course_id: &str, name: &str, version: i32) -> AppResult<Option<Course>> {
+        let updated_course = sqlx::query_as!(
+            Course,
+            r#"
+            UPDATE course SET name = $2, version = version + 1
+            WHERE id = $1 AND version = $3 AND deleted_at IS NULL
+            RETURNING id, name, version
+            "#,
+            course_id,
+            name,
+            version,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(updated_course)
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn check_name(tables: &memory::Tables, course_id: &str, name: &str) -> AppResult<()> {
+        if tables
+            .courses
+            .iter()
+            .any(|c| c.id != course_id && c.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(memory::unique_violation("course_name_key"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn delete(&self, course_id: &str) -> AppResult<bool> {
+        let mut guard = self.database.write();
+        let tables = &mut *guard;
+
+        if tables.students.iter().any(|s| s.course_id == course_id)
+            || !tables.courses.iter().any(|c| c.id == course_id)
+        {
+            return Ok(false);
+        }
+        memory::Tables::trash(
+            &mut tables.courses,
+            &mut tables.trash,
+            time::OffsetDateTime::now_utc(),
+            |c| c.id == course_id,
+            memory::TrashedRow::Course,
+        );
+
+        Ok(true)
+    }
+
+    async fn get_by_id(&self, course_id: &str) -> AppResult<Option<Course>> {
+        let tables = self.database.read();
+
+        Ok(tables.courses.iter().find(|c| c.id == course_id).cloned())
+    }
+
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<Course>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .courses
+            .iter()
+            .find(|c| c.name.to_lowercase() == name.to_lowercase())
+            .cloned())
+    }
+
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<Course>> {
+        if let Some(cursor) = page.anchor() {
+            if !self.database.read().courses.iter().any(|c| c.id == *cursor) {
+                return Err(unknown_cursor("course"));
+            }
+        }
+
+        let courses = self.list_all().await?;
+
+        Ok(memory::paginate(courses, page, |course| &course.id))
+    }
+
+    async fn list_all(&self) -> AppResult<Vec<Course>> {
+        let mut courses = self.database.read().courses.clone();
+        courses.sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+
+        Ok(courses)
+    }
+
+    async fn insert(&self, course: &Course) -> AppResult<Course> {
+        let mut tables = self.database.write();
+
+        if tables.courses.iter().any(|c| c.id == course.id) {
+            return Err(memory::constraint_violation("course_pkey"));
+        }
+        Self::check_name(&tables, &course.id, &course.name)?;
+        tables.courses.push(course.clone());
 
         Ok(course.clone())
     }
+
+    async fn update(&self, course_id: &str, name: &str, version: i32) -> AppResult<Option<Course>> {
+        let mut tables = self.database.write();
+
+        if tables
+            .courses
+            .iter()
+            .any(|c| c.id == course_id && c.version == version)
+        {
+            Self::check_name(&tables, course_id, name)?;
+        }
+
+        let course = tables
+            .courses
+            .iter_mut()
+            .find(|c| c.id == course_id && c.version == version)
+            .map(|course| {
+                course.name = name.to_string();
+                course.version += 1;
+                course.clone()
+            });
+
+        Ok(course)
+    }
 }
@@ -1,54 +1,125 @@
-use std::error::Error;
+use std::sync::Arc;
 
-use crate::student;
-use once_cell::sync::Lazy;
+use tracing::instrument;
 
-use super::{model::Course, repository::Repository};
+use crate::{
+    avatar::repository::Repository as AvatarRepository,
+    error::{AppError, AppResult},
+    pagination::{Page, PageRequest},
+    student::repository::Repository as StudentRepository,
+    subject::repository::Repository as SubjectRepository,
+};
 
-pub static SERVICE: Lazy<Service> = Lazy::new(Service::new);
+use super::{
+    model::{Course, CourseOverview},
+    repository::Repository,
+};
 
 pub struct Service {
-    repository: Repository,
+    repository: Arc<dyn Repository>,
+    student_repository: Arc<dyn StudentRepository>,
+    subject_repository: Arc<dyn SubjectRepository>,
+    avatar_repository: Arc<dyn AvatarRepository>,
 }
 
 impl Service {
-    pub fn new() -> Self {
+    pub fn new(
+        repository: Arc<dyn Repository>,
+        student_repository: Arc<dyn StudentRepository>,
+        subject_repository: Arc<dyn SubjectRepository>,
+        avatar_repository: Arc<dyn AvatarRepository>,
+    ) -> Self {
         Service {
-            repository: Repository::new(),
+            repository,
+            student_repository,
+            subject_repository,
+            avatar_repository,
         }
     }
 
-    pub async fn save(&self, name: &str) -> Result<Course, Box<dyn Error>> {
+    #[instrument(level = "debug", skip(self))]
+    pub async fn save(&self, name: &str) -> AppResult<Course> {
         let course = Course::new(name);
-        self.repository.save(&course).await
+        self.repository.insert(&course).await
     }
 
-    pub async fn edit(&self, id: &str, name: &str) -> Result<Course, Box<dyn Error>> {
-        let course = Course::new_with_id(id, name);
-        self.repository.save(&course).await
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_name(&self, name: &str) -> AppResult<Option<Course>> {
+        self.repository.get_by_name(name).await
     }
 
-    pub async fn delete(&self, course_id: String) -> Result<(), Box<dyn Error>> {
-        let students = student::service::SERVICE
-            .list_students_by_course_id(&course_id)
-            .await?;
+    /// Renames the course, `version` is the one the edit was made against so an edit based on a
+    /// stale copy is refused instead of overwriting the newer name.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn edit(&self, id: &str, name: &str, version: i32) -> AppResult<Course> {
+        if let Some(course) = self.repository.update(id, name, version).await? {
+            return Ok(course);
+        }
+
+        match self.repository.get_by_id(id).await? {
+            None => Err(AppError::NotFound("Course does not exists".to_string())),
+            Some(_) => Err(AppError::Conflict(
+                "The course was changed by someone else, reload it and try again".to_string(),
+            )),
+        }
+    }
 
-        match students.is_empty() {
-            true => self.repository.delete(&course_id).await,
-            false => {
-                Err("Unable to delete the course because exists student that is enrolled".into())
-            }
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete(&self, course_id: String) -> AppResult<()> {
+        if self.repository.get_by_id(&course_id).await?.is_none() {
+            return Err(AppError::NotFound("Course does not exists".to_string()));
         }
+
+        match self.repository.delete(&course_id).await? {
+            true => Ok(()),
+            false => Err(AppError::Conflict(
+                "Unable to delete the course because exists student that is enrolled".to_string(),
+            )),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_course_by_id(&self, course_id: &str) -> AppResult<Option<Course>> {
+        self.repository.get_by_id(course_id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_overview(&self, course_id: &str) -> AppResult<Option<CourseOverview>> {
+        let course = match self.repository.get_by_id(course_id).await? {
+            None => return Ok(None),
+            Some(course) => course,
+        };
+
+        let mut avatars = self.avatar_repository.list_by_course_id(course_id).await?;
+        let students = self
+            .student_repository
+            .list_students_by_course_id(course_id)
+            .await?
+            .into_iter()
+            .map(|student| {
+                let avatar = avatars
+                    .iter()
+                    .position(|a| a.student_id == student.id)
+                    .map(|position| avatars.swap_remove(position));
+
+                (student, avatar)
+            })
+            .collect();
+
+        Ok(Some(CourseOverview {
+            course,
+            students,
+            subjects: self.subject_repository.list_by_course_id(course_id).await?,
+        }))
     }
 
-    pub async fn get_course_by_id(
-        &self,
-        course_id: &str,
-    ) -> Result<Option<Course>, Box<dyn Error>> {
-        self.repository.get_by_id(&course_id).await
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list_courses(&self, page: &PageRequest) -> AppResult<Page<Course>> {
+        self.repository.list(page).await
     }
 
-    pub async fn list_courses(&self) -> Result<Vec<Course>, Box<dyn Error>> {
-        self.repository.list().await
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list_all_courses(&self) -> AppResult<Vec<Course>> {
+        self.repository.list_all().await
     }
 }
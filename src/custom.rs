@@ -1,7 +1,15 @@
-use axum::response::{Html, IntoResponse, Response};
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::{error::AppError, validation::FieldErrors};
 
 pub trait HtmlResponse {
-    fn to_html_response(self) -> impl IntoResponse;
+    fn to_html_response(self) -> Response;
 }
 
 impl HtmlResponse for String {
@@ -9,3 +17,70 @@ impl HtmlResponse for String {
         Html(self).into_response()
     }
 }
+
+#[derive(Debug)]
+pub struct JsonError {
+    status: StatusCode,
+    message: String,
+    fields: Option<FieldErrors>,
+}
+
+impl JsonError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        JsonError {
+            status,
+            message: message.into(),
+            fields: None,
+        }
+    }
+}
+
+impl IntoResponse for JsonError {
+    fn into_response(self) -> Response {
+        let mut error = json!({
+            "status": self.status.as_u16(),
+            "message": self.message,
+        });
+        if let Some(fields) = self.fields {
+            error["fields"] = json!(fields);
+        }
+        let body = json!({ "error": error });
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<JsonRejection> for JsonError {
+    fn from(rejection: JsonRejection) -> Self {
+        JsonError::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for JsonError {
+    fn from(rejection: QueryRejection) -> Self {
+        JsonError::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for JsonError {
+    fn from(rejection: PathRejection) -> Self {
+        JsonError::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<AppError> for JsonError {
+    fn from(err: AppError) -> Self {
+        err.log();
+        let mut json_error = JsonError::new(err.status(), err.public_message());
+        if let AppError::InvalidFields(errors) = err {
+            json_error.fields = Some(errors);
+        }
+        json_error
+    }
+}
+
+impl From<FieldErrors> for JsonError {
+    fn from(errors: FieldErrors) -> Self {
+        AppError::from(errors).into()
+    }
+}
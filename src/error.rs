@@ -0,0 +1,225 @@
+use std::fmt::Display;
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::{custom::HtmlResponse, state::AppState, validation::FieldErrors};
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Unique indexes of the schema, with the conflict reported when another live row already
+/// holds the value.
+const UNIQUE_INDEXES: [(&str, &str); 5] = [
+    ("avatar_student_id_key", "Student already has an avatar!"),
+    (
+        "student_email_key",
+        "Email is already used by another student",
+    ),
+    ("course_name_key", "Name is already used by another course"),
+    (
+        "subject_code_key",
+        "Code is already used by another subject with the same program",
+    ),
+    (
+        "operating_system_name_key",
+        "Name is already used by another operating system",
+    ),
+];
+
+/// Foreign keys that a delete can still break when a row is linked between the check of the
+/// service and the delete, with the conflict reported instead of a database error.
+const FOREIGN_KEYS: [(&str, &str); 1] = [(
+    "student_operating_system_operating_system_id_fkey",
+    "Unable to delete the operating system because a student uses it",
+)];
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    Conflict(String),
+    /// Fields of a form or payload that did not pass `Validate`, shown next to each field.
+    InvalidFields(FieldErrors),
+    BadRequest(String),
+    Unavailable(String),
+    Database(sqlx::Error),
+    Serialization(serde_json::Error),
+    Template(minijinja::Error),
+    Internal(String),
+}
+
+#[derive(Serialize, Clone)]
+struct ErrorHtmlModel {
+    status: u16,
+    reason: &'static str,
+    message: String,
+}
+
+impl AppError {
+    /// Conflict raised when a row breaks one of the unique indexes.
+    pub fn unique_violation(constraint: Option<&str>) -> Self {
+        let message = UNIQUE_INDEXES
+            .iter()
+            .find(|(index, _)| Some(*index) == constraint)
+            .map_or(
+                "The record conflicts with an existing one",
+                |(_, message)| message,
+            );
+
+        AppError::Conflict(message.to_string())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Database(_)
+            | AppError::Serialization(_)
+            | AppError::Template(_)
+            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Server errors are logged with their cause, the client only gets a generic message.
+    pub fn log(&self) {
+        match self {
+            AppError::Unavailable(_) => tracing::warn!(error = %self, "Request refused"),
+            _ if self.status().is_server_error() => tracing::error!(
+                error = %self,
+                source = ?std::error::Error::source(self),
+                "Request failed"
+            ),
+            _ => tracing::debug!(error = %self, "Request rejected"),
+        }
+    }
+
+    /// Message that is safe to show to the client, internal failures are not exposed.
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::BadRequest(message)
+            | AppError::Unavailable(message) => message.clone(),
+            AppError::InvalidFields(_) => "Some fields are invalid".to_string(),
+            AppError::Database(_)
+            | AppError::Serialization(_)
+            | AppError::Template(_)
+            | AppError::Internal(_) => "Internal server error".to_string(),
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound(message) => write!(f, "not found: {message}"),
+            AppError::Conflict(message) => write!(f, "conflict: {message}"),
+            AppError::InvalidFields(errors) => write!(f, "invalid fields: {errors}"),
+            AppError::BadRequest(message) => write!(f, "bad request: {message}"),
+            AppError::Unavailable(message) => write!(f, "unavailable: {message}"),
+            AppError::Database(err) => write!(f, "database: {err}"),
+            AppError::Serialization(err) => write!(f, "serialization: {err}"),
+            AppError::Template(err) => write!(f, "template: {err}"),
+            AppError::Internal(message) => write!(f, "internal: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Database(err) => Some(err),
+            AppError::Serialization(err) => Some(err),
+            AppError::Template(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => AppError::NotFound("Resource not found".to_string()),
+            sqlx::Error::PoolTimedOut => AppError::Unavailable("Database unavailable".to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::unique_violation(db.constraint())
+            }
+            // Other foreign keys are integrity bugs and stay database errors.
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                match FOREIGN_KEYS
+                    .iter()
+                    .find(|(key, _)| Some(*key) == db.constraint())
+                {
+                    Some((_, message)) => AppError::Conflict(message.to_string()),
+                    None => AppError::Database(sqlx::Error::Database(db)),
+                }
+            }
+            err => AppError::Database(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Serialization(err)
+    }
+}
+
+impl From<FieldErrors> for AppError {
+    fn from(errors: FieldErrors) -> Self {
+        AppError::InvalidFields(errors)
+    }
+}
+
+impl From<minijinja::Error> for AppError {
+    fn from(err: minijinja::Error) -> Self {
+        AppError::Template(err)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.log();
+
+        let status = self.status();
+        let context = ErrorHtmlModel {
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or("Error"),
+            message: self.public_message(),
+        };
+
+        let mut response = status.into_response();
+        response.extensions_mut().insert(context);
+        response
+    }
+}
+
+/// Renders the error page for every `AppError` returned by an HTML handler, the error itself
+/// has no access to the application state where the templates live.
+pub async fn render_error_page(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+
+    match response.extensions().get::<ErrorHtmlModel>() {
+        None => response,
+        Some(context) => {
+            let mut page = match state.views.render_template("error", Some(context)) {
+                Ok(html) => html.to_html_response(),
+                // The error page itself is broken, fall back to the bare message.
+                Err(_) => context.message.clone().into_response(),
+            };
+            *page.status_mut() = response.status();
+            page
+        }
+    }
+}
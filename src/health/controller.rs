@@ -0,0 +1,54 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+
+use crate::{
+    error::{AppError, AppResult},
+    health::model::Check,
+    state::AppState,
+};
+
+/// Liveness, answers as long as the process is able to serve requests.
+pub async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness, 503 until the database is reachable, fully migrated and the templates compile.
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let mut checks = state.health.checks().await;
+    checks.push(Check::new(
+        "templates",
+        state.views.check().map_err(|err| err.to_string()),
+    ));
+
+    let ready = checks.iter().all(|check| check.ok);
+    let status = match ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(json!({ "ready": ready, "checks": checks })))
+}
+
+pub async fn status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.health.status().await)
+}
+
+/// Prometheus scrape endpoint, the gauges are refreshed on every scrape.
+pub async fn metrics(State(state): State<AppState>) -> AppResult<impl IntoResponse> {
+    state.metrics.set_pool(state.health.pool_stats());
+    if let Some(counts) = state.health.entity_counts().await {
+        state.metrics.set_entities(&counts);
+    }
+
+    let body = state
+        .metrics
+        .encode()
+        .map_err(|err| AppError::Internal(err.to_string()))?;
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body))
+}
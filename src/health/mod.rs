@@ -0,0 +1,4 @@
+pub mod controller;
+pub mod model;
+pub mod repository;
+pub mod service;
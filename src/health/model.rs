@@ -0,0 +1,55 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    pub fn new(name: &'static str, result: Result<(), String>) -> Self {
+        Check {
+            name,
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub struct EntityCounts {
+    pub students: i64,
+    pub courses: i64,
+    pub subjects: i64,
+    pub avatars: i64,
+}
+
+#[derive(Serialize)]
+pub struct DatabaseStatus {
+    pub ready: bool,
+    pub last_error: Option<String>,
+    pub pool: PoolStats,
+}
+
+#[derive(Serialize)]
+pub struct MigrationStatus {
+    pub expected: Option<i64>,
+    pub applied: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct Status {
+    pub version: &'static str,
+    pub uptime_secs: u64,
+    pub database: DatabaseStatus,
+    pub migrations: MigrationStatus,
+}
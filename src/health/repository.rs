@@ -0,0 +1,121 @@
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{
+    error::AppResult,
+    health::model::{EntityCounts, PoolStats},
+    infra::migrations,
+};
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+use tracing::instrument;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn ping(&self) -> AppResult<()>;
+
+    /// Version of the last migration applied to the database, `None` when none ran yet.
+    async fn latest_migration(&self) -> AppResult<Option<i64>>;
+
+    /// Live rows of each entity, the trashed ones are left out.
+    async fn entity_counts(&self) -> AppResult<EntityCounts>;
+
+    fn pool_stats(&self) -> PoolStats;
+}
+
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "health.ping"))]
+    async fn ping(&self) -> AppResult<()> {
+        sqlx::query!("SELECT 1 AS one")
+            .fetch_one(&self.database)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "health.latest_migration"))]
+    async fn latest_migration(&self) -> AppResult<Option<i64>> {
+        Ok(migrations::latest_applied_version(&self.database).await?)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "health.entity_counts"))]
+    async fn entity_counts(&self) -> AppResult<EntityCounts> {
+        let counts = sqlx::query_as!(
+            EntityCounts,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM student WHERE deleted_at IS NULL) AS "students!",
+                (SELECT COUNT(*) FROM course WHERE deleted_at IS NULL) AS "courses!",
+                (SELECT COUNT(*) FROM subject WHERE deleted_at IS NULL) AS "subjects!",
+                (SELECT COUNT(*) FROM avatar WHERE deleted_at IS NULL) AS "avatars!"
+            "#
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(counts)
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.database.size(),
+            idle: self.database.num_idle(),
+            max_connections: self.database.options().get_max_connections(),
+        }
+    }
+}
+
+/// Always healthy and fully migrated, there is no connection to lose.
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn ping(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn latest_migration(&self) -> AppResult<Option<i64>> {
+        Ok(migrations::latest_version())
+    }
+
+    async fn entity_counts(&self) -> AppResult<EntityCounts> {
+        let tables = self.database.read();
+        // Trashed rows are moved out of the tables, only live ones are counted.
+
+        Ok(EntityCounts {
+            students: tables.students.len() as i64,
+            courses: tables.courses.len() as i64,
+            subjects: tables.subjects.len() as i64,
+            avatars: tables.avatars.len() as i64,
+        })
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: 0,
+            idle: 0,
+            max_connections: 0,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{sync::Arc, time::Instant};
+
+use tracing::instrument;
+
+use crate::infra::{db, migrations};
+
+use super::{
+    model::{Check, DatabaseStatus, EntityCounts, MigrationStatus, PoolStats, Status},
+    repository::Repository,
+};
+
+pub struct Service {
+    repository: Arc<dyn Repository>,
+    database: Arc<db::DatabaseStatus>,
+    started_at: Instant,
+}
+
+impl Service {
+    pub fn new(repository: Arc<dyn Repository>, database: Arc<db::DatabaseStatus>) -> Self {
+        Service {
+            repository,
+            database,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Database and migration checks, both fail without querying while the startup connection
+    /// is still being retried so the probe never waits on the pool.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn checks(&self) -> Vec<Check> {
+        if !self.database.is_ready() {
+            let unavailable = || Err("database unavailable".to_string());
+            return vec![
+                Check::new("database", unavailable()),
+                Check::new("migrations", unavailable()),
+            ];
+        }
+
+        let database = self.repository.ping().await.map_err(|err| {
+            self.database.set_error(&err);
+            err.to_string()
+        });
+
+        let expected = migrations::latest_version();
+        let migrations = match self.repository.latest_migration().await {
+            Ok(applied) if applied == expected => Ok(()),
+            Ok(applied) => Err(format!(
+                "latest applied migration is {}, expected {}",
+                version(applied),
+                version(expected)
+            )),
+            Err(err) => Err(err.to_string()),
+        };
+
+        vec![
+            Check::new("database", database),
+            Check::new("migrations", migrations),
+        ]
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.repository.pool_stats()
+    }
+
+    /// `None` while the database is unavailable, counting would only wait on the pool.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn entity_counts(&self) -> Option<EntityCounts> {
+        if !self.database.is_ready() {
+            return None;
+        }
+
+        match self.repository.entity_counts().await {
+            Ok(counts) => Some(counts),
+            Err(err) => {
+                err.log();
+                None
+            }
+        }
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub async fn status(&self) -> Status {
+        let ready = self.database.is_ready();
+        let applied = match ready {
+            true => self.repository.latest_migration().await.ok().flatten(),
+            false => None,
+        };
+
+        Status {
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            database: DatabaseStatus {
+                ready,
+                last_error: self.database.last_error(),
+                pool: self.repository.pool_stats(),
+            },
+            migrations: MigrationStatus {
+                expected: migrations::latest_version(),
+                applied,
+            },
+        }
+    }
+}
+
+fn version(version: Option<i64>) -> String {
+    version.map_or("none".to_string(), |version| version.to_string())
+}
@@ -1,33 +1,152 @@
-use once_cell::sync::OnceCell;
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::{
+    fmt::Display,
+    io,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
 
-pub static DB_POOL: OnceCell<Pool<Postgres>> = OnceCell::new();
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    Connection, PgConnection, Pool, Postgres,
+};
 
-pub async fn start_connection() -> Result<(), sqlx::Error> {
-    let postgres_user = std::env::var("POSTGRES_USER").expect("POSTGRES_USER must be set.");
-    let postgres_password =
-        std::env::var("POSTGRES_PASSWORD").expect("POSTGRES_PASSWORD must be set.");
-    let postgres_db = std::env::var("POSTGRES_DB").expect("POSTGRES_DB must be set.");
-    let postgres_port = std::env::var("POSTGRES_PORT").expect("POSTGRES_PORT must be set.");
-    let postgres_host = std::env::var("POSTGRES_HOST").expect("POSTGRES_HOST must be set.");
+use crate::{config::DatabaseConfig, custom::JsonError, error::AppError, state::AppState};
 
-    let url = format!(
-        "postgres://{}:{}@{}:{}/{}",
-        postgres_user, postgres_password, postgres_host, postgres_port, postgres_db
-    );
+/// Whether the database has been reached since startup, shared with the request handlers.
+#[derive(Default)]
+pub struct DatabaseStatus {
+    ready: AtomicBool,
+    last_error: RwLock<Option<String>>,
+}
+
+impl DatabaseStatus {
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .read()
+            .expect("database status lock poisoned")
+            .clone()
+    }
+
+    pub fn set_error(&self, err: impl Display) {
+        *self
+            .last_error
+            .write()
+            .expect("database status lock poisoned") = Some(err.to_string());
+    }
+}
+
+/// The `sslmode` of the URL, `prefer` when it has none, is only replaced by an explicit
+/// `ssl_mode`.
+fn connect_options(config: &DatabaseConfig) -> Result<PgConnectOptions, sqlx::Error> {
+    let options = PgConnectOptions::from_str(&config.url)?;
+
+    Ok(match config.ssl_mode {
+        Some(ssl_mode) => options.ssl_mode(ssl_mode),
+        None => options,
+    })
+}
 
+/// Builds the pool without opening any connection, so the server can start before Postgres.
+pub fn connect_lazy(config: &DatabaseConfig) -> Result<Pool<Postgres>, sqlx::Error> {
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&url)
-        .await?;
+        .min_connections(config.min_connections)
+        .max_connections(config.max_connections)
+        .idle_timeout(config.idle_timeout)
+        .acquire_timeout(config.acquire_timeout)
+        .connect_lazy_with(connect_options(config)?);
 
-    let result = DB_POOL.set(pool);
+    Ok(pool)
+}
+
+/// Tries to open a connection up to `connect_attempts` times, doubling the delay between
+/// attempts up to `connect_backoff_max`. Every failure is logged and kept in `status`.
+pub async fn wait_for_database(
+    config: &DatabaseConfig,
+    status: &DatabaseStatus,
+) -> Result<(), sqlx::Error> {
+    let options = connect_options(config)?;
+    let mut delay = config.connect_backoff;
+    let mut attempt = 1;
+
+    loop {
+        let result =
+            tokio::time::timeout(config.acquire_timeout, PgConnection::connect_with(&options))
+                .await
+                .unwrap_or_else(|_| Err(sqlx::Error::Io(io::ErrorKind::TimedOut.into())));
 
-    match result {
-        Err(_) => panic!("Unable connect to database"),
-        Ok(_) => {
-            println!("Database Connected");
-            Ok(())
+        match result {
+            Ok(connection) => {
+                connection.close().await?;
+                tracing::info!(attempt, "Database connected");
+                return Ok(());
+            }
+            Err(err) => {
+                status.set_error(&err);
+                if attempt >= config.connect_attempts {
+                    tracing::error!(
+                        attempt,
+                        attempts = config.connect_attempts,
+                        error = %err,
+                        "Database connection failed"
+                    );
+                    return Err(err);
+                }
+
+                tracing::warn!(
+                    attempt,
+                    attempts = config.connect_attempts,
+                    retry_in_secs = delay.as_secs(),
+                    error = %err,
+                    "Database connection failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(config.connect_backoff_max);
+                attempt += 1;
+            }
         }
     }
 }
+
+fn unavailable() -> AppError {
+    AppError::Unavailable("Database unavailable".to_string())
+}
+
+/// Answers 503 on the HTML routes until `wait_for_database` succeeds.
+pub async fn require_database(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match state.database.is_ready() {
+        true => next.run(request).await,
+        false => unavailable().into_response(),
+    }
+}
+
+/// Same as `require_database` with the JSON error body used by the API routes.
+pub async fn require_database_json(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match state.database.is_ready() {
+        true => next.run(request).await,
+        false => JsonError::from(unavailable()).into_response(),
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use time::OffsetDateTime;
+
+use crate::{
+    avatar::model::Avatar,
+    course::model::Course,
+    error::AppError,
+    language::model::Language,
+    operating_system::model::OperatingSystem,
+    pagination::{Page, PageRequest},
+    student::model::Student,
+    subject::model::Subject,
+    trash::model::TrashKind,
+};
+
+#[derive(Default)]
+pub struct Tables {
+    pub courses: Vec<Course>,
+    pub students: Vec<Student>,
+    pub avatars: Vec<Avatar>,
+    pub subjects: Vec<Subject>,
+    pub subject_courses: Vec<(String, String)>,
+    pub operating_systems: Vec<OperatingSystem>,
+    pub languages: Vec<Language>,
+    /// Rows with `deleted_at` set are kept apart, so the other tables only hold live rows.
+    pub trash: Vec<Trashed>,
+}
+
+#[derive(Clone)]
+pub enum TrashedRow {
+    Course(Course),
+    Student(Student),
+    Subject(Subject),
+    Avatar(Avatar),
+}
+
+impl TrashedRow {
+    pub fn kind(&self) -> TrashKind {
+        match self {
+            TrashedRow::Course(_) => TrashKind::Course,
+            TrashedRow::Student(_) => TrashKind::Student,
+            TrashedRow::Subject(_) => TrashKind::Subject,
+            TrashedRow::Avatar(_) => TrashKind::Avatar,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            TrashedRow::Course(course) => &course.id,
+            TrashedRow::Student(student) => &student.id,
+            TrashedRow::Subject(subject) => &subject.id,
+            TrashedRow::Avatar(avatar) => &avatar.id,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Trashed {
+    pub row: TrashedRow,
+    pub deleted_at: OffsetDateTime,
+}
+
+impl Tables {
+    /// Moves the matching rows to the trash, like setting `deleted_at` in Postgres.
+    pub fn trash<T>(
+        rows: &mut Vec<T>,
+        trash: &mut Vec<Trashed>,
+        deleted_at: OffsetDateTime,
+        matches: impl Fn(&T) -> bool,
+        wrap: impl Fn(T) -> TrashedRow,
+    ) {
+        let (trashed, kept) = std::mem::take(rows)
+            .into_iter()
+            .partition(|row| matches(row));
+        *rows = kept;
+
+        trash.extend(trashed.into_iter().map(|row: T| Trashed {
+            row: wrap(row),
+            deleted_at,
+        }));
+    }
+
+    pub fn trashed(&self, kind: TrashKind, id: &str) -> Option<&Trashed> {
+        self.trash
+            .iter()
+            .find(|trashed| trashed.row.kind() == kind && trashed.row.id() == id)
+    }
+}
+
+/// Shared in-memory tables used by the `InMemoryRepository` of every entity, so joins between
+/// entities see the same data just like they would in Postgres.
+#[derive(Clone, Default)]
+pub struct Database {
+    tables: Arc<RwLock<Tables>>,
+}
+
+impl Database {
+    /// Starts with the operating systems and languages seeded by the migrations.
+    pub fn new() -> Self {
+        let database = Database::default();
+        let mut tables = database.write();
+        tables.operating_systems = ["Linux", "macOS", "Windows"]
+            .into_iter()
+            .map(OperatingSystem::new)
+            .collect();
+        tables.languages = [
+            ("de", "German"),
+            ("en", "English"),
+            ("en-GB", "English (United Kingdom)"),
+            ("en-US", "English (United States)"),
+            ("es", "Spanish"),
+            ("fr", "French"),
+            ("it", "Italian"),
+            ("ja", "Japanese"),
+            ("pt", "Portuguese"),
+            ("pt-BR", "Portuguese (Brazil)"),
+            ("pt-PT", "Portuguese (Portugal)"),
+            ("zh", "Chinese"),
+            ("und", "Undetermined"),
+        ]
+        .into_iter()
+        .map(|(code, name)| Language {
+            code: code.to_string(),
+            name: name.to_string(),
+        })
+        .collect();
+        drop(tables);
+
+        database
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables
+            .read()
+            .expect("in-memory database lock poisoned")
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables
+            .write()
+            .expect("in-memory database lock poisoned")
+    }
+}
+
+/// Mirrors the error raised by Postgres when a constraint is violated.
+pub fn constraint_violation(constraint: &str) -> AppError {
+    AppError::Database(sqlx::Error::Protocol(format!(
+        "violates constraint {constraint}"
+    )))
+}
+
+/// Mirrors the conflict reported when Postgres refuses a row because of a unique index.
+pub fn unique_violation(index: &str) -> AppError {
+    AppError::unique_violation(Some(index))
+}
+
+/// Inserts the row or replaces the one with the same id, like `ON CONFLICT (id) DO UPDATE`.
+pub fn upsert<T: Clone>(rows: &mut Vec<T>, row: T, same_id: impl Fn(&T) -> bool) {
+    match rows.iter_mut().find(|existing| same_id(existing)) {
+        Some(existing) => *existing = row,
+        None => rows.push(row),
+    }
+}
+
+/// Pages rows that are already sorted, the same way the keyset queries do in Postgres.
+pub fn paginate<T>(rows: Vec<T>, request: &PageRequest, id: impl Fn(&T) -> &str) -> Page<T> {
+    let total = rows.len() as i64;
+
+    let resolved;
+    let request = match &request.before {
+        Some(before) => {
+            let position = rows
+                .iter()
+                .position(|row| id(row) == before)
+                .unwrap_or(rows.len());
+            resolved = request.ending_before(position as i64);
+            &resolved
+        }
+        None => request,
+    };
+
+    let start = match &request.cursor {
+        Some(cursor) => rows
+            .iter()
+            .position(|row| id(row) == cursor)
+            .map_or(rows.len(), |position| position + 1),
+        None => request.offset() as usize,
+    };
+
+    let rows = rows
+        .into_iter()
+        .skip(start)
+        .take(request.limit() as usize)
+        .collect();
+
+    Page::new(rows, total, request, id)
+}
@@ -0,0 +1,165 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    histogram_opts, opts, Encoder, HistogramVec, IntCounterVec, IntGaugeVec, Registry, TextEncoder,
+};
+
+use crate::{
+    health::model::{EntityCounts, PoolStats},
+    state::AppState,
+};
+
+/// Filled by `telemetry::QueryTimer` from the repository spans. Spans are process wide, so the
+/// histogram is shared by the registry of every `Metrics`.
+pub static QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    HistogramVec::new(
+        histogram_opts!(
+            "db_query_duration_seconds",
+            "Time spent in each repository method"
+        ),
+        &["query"],
+    )
+    .expect("valid query duration histogram")
+});
+
+/// Prometheus collectors of one application instance.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+    entities: IntGaugeVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            opts!(
+                "http_requests_total",
+                "Requests answered per route and status"
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("valid request counter");
+        let http_duration = HistogramVec::new(
+            histogram_opts!(
+                "http_request_duration_seconds",
+                "Time to answer a request per route"
+            ),
+            &["method", "route"],
+        )
+        .expect("valid request histogram");
+        let pool_connections = IntGaugeVec::new(
+            opts!(
+                "db_pool_connections",
+                "Connections of the database pool by state"
+            ),
+            &["state"],
+        )
+        .expect("valid pool gauge");
+        let entities = IntGaugeVec::new(
+            opts!(
+                "entities_total",
+                "Live rows per entity, trashed ones excluded"
+            ),
+            &["entity"],
+        )
+        .expect("valid entity gauge");
+
+        let registry = Registry::new();
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_duration.clone()),
+            Box::new(pool_connections.clone()),
+            Box::new(entities.clone()),
+            Box::new(QUERY_DURATION.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metrics are registered once per registry");
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_duration,
+            pool_connections,
+            entities,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn set_pool(&self, stats: PoolStats) {
+        let gauge =
+            |state: &str, value: i64| self.pool_connections.with_label_values(&[state]).set(value);
+        gauge("open", stats.size.into());
+        gauge("idle", stats.idle as i64);
+        gauge("in_use", i64::from(stats.size) - stats.idle as i64);
+        gauge("max", stats.max_connections.into());
+    }
+
+    pub fn set_entities(&self, counts: &EntityCounts) {
+        for (entity, count) in [
+            ("student", counts.students),
+            ("course", counts.courses),
+            ("subject", counts.subjects),
+            ("avatar", counts.avatars),
+        ] {
+            self.entities.with_label_values(&[entity]).set(count);
+        }
+    }
+
+    /// Renders every collector in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Records every request under its route template, so `/student/:student_id` is one series.
+pub async fn track_metrics(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_string(), |path| path.as_str().to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    state.metrics.observe_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
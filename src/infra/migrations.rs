@@ -0,0 +1,86 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    Pool, Postgres,
+};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// SQLSTATE of a query on a table that does not exist.
+const UNDEFINED_TABLE: &str = "42P01";
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Version of the newest embedded migration, the one a fully migrated database is at.
+pub fn latest_version() -> Option<i64> {
+    MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| migration.version)
+        .max()
+}
+
+pub async fn up(pool: &Pool<Postgres>) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Reverts the most recently applied migration.
+pub async fn down(pool: &Pool<Postgres>) -> Result<Option<i64>, MigrateError> {
+    let mut applied = applied_versions(pool).await?;
+    applied.sort_unstable();
+
+    let Some(last) = applied.pop() else {
+        return Ok(None);
+    };
+    let target = applied.pop().unwrap_or(0);
+
+    MIGRATOR.undo(pool, target).await?;
+
+    Ok(Some(last))
+}
+
+pub async fn status(pool: &Pool<Postgres>) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let applied = applied_versions(pool).await?;
+
+    let statuses = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+/// Version of the last migration applied, read by the probes. Unlike the `migrate` subcommand it
+/// never creates the migrations table, a database without it has no migration applied.
+pub async fn latest_applied_version(pool: &Pool<Postgres>) -> Result<Option<i64>, sqlx::Error> {
+    let version = sqlx::query_scalar("SELECT max(version) FROM _sqlx_migrations WHERE success")
+        .fetch_one(pool)
+        .await;
+
+    match version {
+        Err(sqlx::Error::Database(db)) if db.code().as_deref() == Some(UNDEFINED_TABLE) => Ok(None),
+        version => version,
+    }
+}
+
+async fn applied_versions(pool: &Pool<Postgres>) -> Result<Vec<i64>, MigrateError> {
+    let mut connection = pool.acquire().await?;
+    connection.ensure_migrations_table().await?;
+
+    let applied = connection
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(applied)
+}
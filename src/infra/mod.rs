@@ -1 +1,7 @@
 pub mod db;
+#[cfg(test)]
+pub mod memory;
+pub mod metrics;
+pub mod migrations;
+pub mod shutdown;
+pub mod telemetry;
@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+/// Counts the requests being served, so shutdown can report what was drained and what was cut.
+#[derive(Default)]
+pub struct RequestTracker {
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+}
+
+impl RequestTracker {
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Acquire)
+    }
+}
+
+/// Decrements the in-flight count even when the request future is dropped halfway.
+struct InFlight<'a>(&'a RequestTracker);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub async fn track_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    state.requests.in_flight.fetch_add(1, Ordering::AcqRel);
+    let _guard = InFlight(&state.requests);
+
+    let response = next.run(request).await;
+    state.requests.completed.fetch_add(1, Ordering::AcqRel);
+    response
+}
+
+/// Resolves with the name of the first SIGINT or SIGTERM received.
+pub async fn signal() -> &'static str {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("unable to listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("unable to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => "SIGINT",
+        _ = terminate => "SIGTERM",
+    }
+}
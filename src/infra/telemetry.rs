@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+use axum::{extract::Request, http::HeaderName};
+use tower_http::classify::ServerErrorsFailureClass;
+use tracing::{
+    field::{Field, Visit},
+    span, Span, Subscriber,
+};
+use tracing_subscriber::{
+    filter::{filter_fn, LevelFilter},
+    fmt::{self, format::FmtSpan},
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::{config::Config, infra::metrics::QUERY_DURATION};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Installs the global subscriber. `RUST_LOG` takes precedence over the configured level. Span
+/// closings are only logged at the debug level, where service and query spans report their
+/// duration, since `TraceLayer` already logs every request.
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{},sqlx=warn", config.log_level)));
+
+    let span_events = match filter.max_level_hint() {
+        Some(level) if level >= LevelFilter::DEBUG => FmtSpan::CLOSE,
+        _ => FmtSpan::NONE,
+    };
+    let logs = match config.log_format.as_str() {
+        "json" => fmt::layer().json().with_span_events(span_events).boxed(),
+        _ => fmt::layer().with_span_events(span_events).boxed(),
+    };
+
+    // The query spans are always built for the metrics, whatever the log level is.
+    let queries = QueryTimer.with_filter(filter_fn(|metadata| {
+        metadata.is_span() && metadata.fields().field("query").is_some()
+    }));
+
+    tracing_subscriber::registry()
+        .with(logs.with_filter(filter))
+        .with(queries)
+        .init();
+}
+
+/// Root span of every request, the id is set by `SetRequestIdLayer` before the span is built.
+pub fn request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
+
+/// Failures seen by `TraceLayer`. The 5xx responses are answered by the application, which already
+/// logged the `AppError` behind them at its level or, for `/readyz`, only report that the service
+/// is not ready, so they are left to the debug level. Errors of the services are still errors.
+pub fn on_failure(failure: ServerErrorsFailureClass, latency: Duration, _: &Span) {
+    match failure {
+        ServerErrorsFailureClass::StatusCode(status) => {
+            tracing::debug!(%status, latency = ?latency, "Response failed")
+        }
+        ServerErrorsFailureClass::Error(error) => {
+            tracing::error!(error, latency = ?latency, "Response failed")
+        }
+    }
+}
+
+/// Feeds `QUERY_DURATION` with the lifetime of every span that has a `query` field, which are
+/// the spans of the Postgres repository methods.
+pub struct QueryTimer;
+
+struct QueryStart {
+    query: String,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct QueryName(Option<String>);
+
+impl Visit for QueryName {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "query" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+impl<S> Layer<S> for QueryTimer
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut name = QueryName::default();
+        attrs.record(&mut name);
+
+        if let (Some(query), Some(span)) = (name.0, ctx.span(id)) {
+            span.extensions_mut().insert(QueryStart {
+                query,
+                started: Instant::now(),
+            });
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let extensions = span.extensions();
+        if let Some(start) = extensions.get::<QueryStart>() {
+            QUERY_DURATION
+                .with_label_values(&[&start.query])
+                .observe(start.started.elapsed().as_secs_f64());
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use super::model::Language;
+use crate::{custom::JsonError, state::AppState};
+use axum::{extract::State, Json};
+
+/// The catalog, students are created and filtered with the codes it lists.
+pub async fn list_languages_json(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Language>>, JsonError> {
+    Ok(Json(state.languages.list_all_languages().await?))
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Language of the catalog, students refer to it by its code.
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct Language {
+    /// BCP-47 tag, e.g. `en` or `pt-BR`, kept by the students and used by the filters.
+    pub code: String,
+    pub name: String,
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{error::AppResult, language::model::Language};
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+use tracing::instrument;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn get_by_code(&self, code: &str) -> AppResult<Option<Language>>;
+
+    /// The whole catalog ordered by name, for the select boxes of the student forms.
+    async fn list_all(&self) -> AppResult<Vec<Language>>;
+}
+
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "language.get_by_code"))]
+    async fn get_by_code(&self, code: &str) -> AppResult<Option<Language>> {
+        let language = sqlx::query_as!(
+            Language,
+            r#"
+            SELECT code, name FROM language WHERE code = $1
+            "#,
+            code
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(language)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "language.list_all"))]
+    async fn list_all(&self) -> AppResult<Vec<Language>> {
+        let languages = sqlx::query_as!(
+            Language,
+            r#"
+            SELECT code, name FROM language ORDER BY lower(name), code
+            "#,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(languages)
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn get_by_code(&self, code: &str) -> AppResult<Option<Language>> {
+        let tables = self.database.read();
+
+        Ok(tables.languages.iter().find(|l| l.code == code).cloned())
+    }
+
+    async fn list_all(&self) -> AppResult<Vec<Language>> {
+        let mut languages = self.database.read().languages.clone();
+        languages.sort_by(|a, b| {
+            (a.name.to_lowercase(), &a.code).cmp(&(b.name.to_lowercase(), &b.code))
+        });
+
+        Ok(languages)
+    }
+}
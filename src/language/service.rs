@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+
+use crate::error::AppResult;
+
+use super::{model::Language, repository::Repository};
+
+pub struct Service {
+    repository: Arc<dyn Repository>,
+}
+
+impl Service {
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Service { repository }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_code(&self, code: &str) -> AppResult<Option<Language>> {
+        self.repository.get_by_code(code).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn list_all_languages(&self) -> AppResult<Vec<Language>> {
+        self.repository.list_all().await
+    }
+}
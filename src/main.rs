@@ -1,26 +1,201 @@
 mod avatar;
+mod cli;
+mod config;
 mod course;
 mod custom;
+mod error;
+mod health;
 mod infra;
+mod language;
 mod menu;
+mod operating_system;
+mod pagination;
+mod state;
 mod student;
 mod subject;
+#[cfg(test)]
+mod tests;
+mod trash;
+mod validation;
 mod view;
 
-use std::fmt::Error;
+use std::{
+    cell::Cell,
+    fmt::Error,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
-    routing::{delete, get},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
+use clap::Parser;
+use cli::{Cli, Command, MigrateAction};
+use config::{Config, DatabaseConfig};
 use dotenv::dotenv;
+use infra::db::DatabaseStatus;
+use infra::telemetry::REQUEST_ID_HEADER;
+use sqlx::{Pool, Postgres};
+use state::AppState;
+use tokio::sync::oneshot;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::ServeDir,
+    trace::TraceLayer,
+};
+use view::{NavLink, NavSection, Views};
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    dotenv().ok();
-    infra::db::start_connection().await.unwrap();
+/// How often the records that outlived the trash retention period are purged.
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Pages rendered in the navigation bar and on the menu, the first link of each section is the
+/// one shown in the bar. Keep in sync with the HTML routes below.
+pub const NAVIGATION: &[NavSection] = &[
+    NavSection {
+        title: "Students",
+        links: &[
+            NavLink {
+                label: "Student List",
+                href: "/students",
+            },
+            NavLink {
+                label: "Create a Student",
+                href: "/student/create",
+            },
+        ],
+    },
+    NavSection {
+        title: "Courses",
+        links: &[
+            NavLink {
+                label: "Course List",
+                href: "/courses",
+            },
+            NavLink {
+                label: "Create a Course",
+                href: "/course/create",
+            },
+        ],
+    },
+    NavSection {
+        title: "Avatars",
+        links: &[
+            NavLink {
+                label: "Avatar List",
+                href: "/avatars",
+            },
+            NavLink {
+                label: "Create an Avatar",
+                href: "/avatar/create",
+            },
+        ],
+    },
+    NavSection {
+        title: "Subjects",
+        links: &[
+            NavLink {
+                label: "Subject List",
+                href: "/subjects",
+            },
+            NavLink {
+                label: "Create a Subject",
+                href: "/subject/create",
+            },
+        ],
+    },
+    NavSection {
+        title: "Operating Systems",
+        links: &[
+            NavLink {
+                label: "Operating System List",
+                href: "/operating-systems",
+            },
+            NavLink {
+                label: "Create an Operating System",
+                href: "/operating-system/create",
+            },
+        ],
+    },
+    NavSection {
+        title: "Trash",
+        links: &[NavLink {
+            label: "Deleted Records",
+            href: "/trash",
+        }],
+    },
+];
+
+fn router(state: AppState, assets_dir: &Path) -> Router {
+    let api = Router::new()
+        .route(
+            "/students",
+            get(student::controller::list_students_json)
+                .post(student::controller::create_student_json),
+        )
+        .route(
+            "/students/:student_id",
+            get(student::controller::show_student_json)
+                .put(student::controller::update_student_json)
+                .patch(student::controller::patch_student_json)
+                .delete(student::controller::delete_student_json),
+        )
+        .route(
+            "/courses",
+            get(course::controller::list_courses_json).post(course::controller::create_course_json),
+        )
+        .route(
+            "/courses/:course_id",
+            get(course::controller::show_course_json)
+                .put(course::controller::update_course_json)
+                .delete(course::controller::delete_course_json),
+        )
+        .route(
+            "/subjects",
+            get(subject::controller::list_json).post(subject::controller::create_json),
+        )
+        .route(
+            "/subjects/:subject_id",
+            get(subject::controller::show_json)
+                .put(subject::controller::update_json)
+                .delete(subject::controller::delete_json),
+        )
+        .route(
+            "/avatars",
+            get(avatar::controller::list_avatars_json).post(avatar::controller::create_avatar_json),
+        )
+        .route(
+            "/avatars/:avatar_id",
+            get(avatar::controller::show_avatar_json)
+                .put(avatar::controller::update_avatar_json)
+                .delete(avatar::controller::delete_avatar_json),
+        )
+        .route("/languages", get(language::controller::list_languages_json))
+        .route(
+            "/operating-systems",
+            get(operating_system::controller::list_operating_systems_json)
+                .post(operating_system::controller::create_operating_system_json),
+        )
+        .route(
+            "/operating-systems/:operating_system_id",
+            get(operating_system::controller::show_operating_system_json)
+                .put(operating_system::controller::update_operating_system_json)
+                .delete(operating_system::controller::delete_operating_system_json),
+        )
+        .route("/trash", get(trash::controller::list_json))
+        .route("/trash/:kind/:id", delete(trash::controller::purge_json))
+        .route(
+            "/trash/:kind/:id/restore",
+            post(trash::controller::restore_json),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            infra::db::require_database_json,
+        ));
 
-    let app = Router::new()
+    Router::new()
         .route("/", get(menu::controller::render_student_list))
         .route("/students", get(student::controller::student_list_html))
         .route(
@@ -31,6 +206,11 @@ async fn main() -> Result<(), Error> {
             "/student/:student_id",
             delete(student::controller::delete_student).get(student::controller::show_student_html),
         )
+        .route(
+            "/student/:student_id/edit",
+            get(student::controller::edit_student_form_html)
+                .post(student::controller::edit_student),
+        )
         .route(
             "/student/create",
             get(student::controller::create_student_form_html)
@@ -54,15 +234,268 @@ async fn main() -> Result<(), Error> {
             "/avatar/create",
             get(avatar::controller::create_avatar_html).post(avatar::controller::create_avatar),
         )
+        .route(
+            "/avatar/:avatar_id",
+            get(avatar::controller::show_avatar_html).delete(avatar::controller::delete_avatar),
+        )
+        .route(
+            "/avatar/:avatar_id/edit",
+            get(avatar::controller::edit_avatar_html).post(avatar::controller::edit_avatar),
+        )
         .route("/avatars", get(avatar::controller::list_avatar_html))
         .route(
             "/subject/create",
             get(subject::controller::create_html).post(subject::controller::create),
         )
-        .route("/subjects", get(subject::controller::list_html));
+        .route(
+            "/subject/:subject_id",
+            get(subject::controller::show_html).delete(subject::controller::delete),
+        )
+        .route(
+            "/subject/:subject_id/edit",
+            get(subject::controller::edit_html).post(subject::controller::edit),
+        )
+        .route("/subjects", get(subject::controller::list_html))
+        .route(
+            "/operating-systems",
+            get(operating_system::controller::operating_system_list_html),
+        )
+        .route(
+            "/operating-system/create",
+            get(operating_system::controller::create_operating_system_form_html)
+                .post(operating_system::controller::create_operating_system),
+        )
+        .route(
+            "/operating-system/:operating_system_id",
+            get(operating_system::controller::show_operating_system_html)
+                .delete(operating_system::controller::delete_operating_system),
+        )
+        .route(
+            "/operating-system/:operating_system_id/edit",
+            get(operating_system::controller::edit_operating_system_form_html)
+                .post(operating_system::controller::edit_operating_system),
+        )
+        .route("/trash", get(trash::controller::trash_html))
+        .route("/trash/:kind/:id", delete(trash::controller::purge))
+        .route("/trash/:kind/:id/restore", post(trash::controller::restore))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            infra::db::require_database,
+        ))
+        .route("/healthz", get(health::controller::healthz))
+        .route("/readyz", get(health::controller::readyz))
+        .route("/status", get(health::controller::status))
+        .route("/metrics", get(health::controller::metrics))
+        .nest("/api/v1", api)
+        .nest_service("/assets", ServeDir::new(assets_dir))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            error::render_error_page,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            infra::metrics::track_metrics,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            infra::shutdown::track_requests,
+        ))
+        // Layers run bottom to top: the id is generated, then the span reads it, then the
+        // response echoes it back.
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(infra::telemetry::request_span)
+                .on_failure(infra::telemetry::on_failure),
+        )
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+        .with_state(state)
+}
+
+async fn migrate(pool: &Pool<Postgres>, action: MigrateAction) {
+    let result = match action {
+        MigrateAction::Up => infra::migrations::up(pool)
+            .await
+            .map(|_| tracing::info!("Migrations applied")),
+        MigrateAction::Down => infra::migrations::down(pool)
+            .await
+            .map(|reverted| match reverted {
+                Some(version) => tracing::info!(version, "Reverted migration"),
+                None => tracing::info!("No migration to revert"),
+            }),
+        MigrateAction::Status => infra::migrations::status(pool).await.map(|migrations| {
+            for migration in migrations {
+                let state = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!(
+                    "{} {:<8} {}",
+                    migration.version, state, migration.description
+                );
+            }
+        }),
+    };
+
+    if let Err(err) = result {
+        tracing::error!(error = %err, "Migration failed");
+        std::process::exit(1);
+    }
+}
+
+/// Waits for Postgres in the background while the server answers 503, then opens the routes.
+async fn connect(
+    pool: Pool<Postgres>,
+    config: DatabaseConfig,
+    status: Arc<DatabaseStatus>,
+    migrate_on_start: bool,
+) {
+    if let Err(err) = infra::db::wait_for_database(&config, &status).await {
+        tracing::error!(error = %err, "Giving up on the database");
+        std::process::exit(1);
+    }
+
+    if migrate_on_start {
+        migrate(&pool, MigrateAction::Up).await;
+    }
+
+    status.set_ready();
+}
+
+/// Purges what stayed in the trash longer than `retention`, skipping runs while the database is
+/// not ready.
+async fn purge_trash(state: AppState, retention: Duration) {
+    let mut interval = tokio::time::interval(TRASH_PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        if !state.database.is_ready() {
+            continue;
+        }
+
+        match state.trash.purge_expired(retention).await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!(purged, "Purged expired records from the trash"),
+            Err(err) => tracing::warn!(error = %err, "Unable to purge the trash"),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    dotenv().ok();
+
+    let config = match Config::load(&cli.config) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    infra::telemetry::init(&config);
+
+    let pool = match infra::db::connect_lazy(&config.database) {
+        Ok(pool) => pool,
+        Err(err) => {
+            tracing::error!(error = %err, "Configuration error: invalid `database.url`");
+            std::process::exit(1);
+        }
+    };
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Migrate { action } => {
+            let status = DatabaseStatus::default();
+            if let Err(err) = infra::db::wait_for_database(&config.database, &status).await {
+                tracing::error!(error = %err, "Giving up on the database");
+                std::process::exit(1);
+            }
+
+            migrate(&pool, action).await;
+            return Ok(());
+        }
+        Command::Serve => {}
+    }
+
+    let state = AppState::postgres(pool.clone(), Views::new(&config.template_dir));
+    let requests = state.requests.clone();
+    tokio::spawn(connect(
+        pool.clone(),
+        config.database.clone(),
+        state.database.clone(),
+        cli.migrate_on_start,
+    ));
+    tokio::spawn(purge_trash(state.clone(), config.trash_retention));
+
+    let app = router(state, &config.assets_dir);
+
+    let listener = tokio::net::TcpListener::bind(config.listen_addr)
+        .await
+        .unwrap();
+    tracing::info!(address = %config.listen_addr, "Listening");
+
+    // Set when the signal arrives, the drain deadline starts counting from there.
+    let (signalled, signal_received) = oneshot::channel();
+    let in_flight = requests.clone();
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        let signal = infra::shutdown::signal().await;
+        tracing::info!(
+            signal,
+            in_flight = in_flight.in_flight(),
+            "Draining in-flight requests"
+        );
+        let _ = signalled.send(Instant::now());
+    });
+
+    let drain_started = Cell::new(None);
+    let deadline = async {
+        match signal_received.await {
+            Ok(started) => {
+                drain_started.set(Some(started));
+                tokio::time::sleep(config.shutdown_timeout).await
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = async { server.await } => result.unwrap(),
+        _ = deadline => tracing::warn!(
+            deadline_secs = config.shutdown_timeout.as_secs(),
+            abandoned = requests.in_flight(),
+            "Shutdown deadline reached, abandoning in-flight requests"
+        ),
+    }
+
+    // Closing waits for every connection to come back, abandoned requests may still hold one
+    // so it only gets what is left of the deadline.
+    let remaining = drain_started
+        .get()
+        .map_or(config.shutdown_timeout, |started| {
+            config.shutdown_timeout.saturating_sub(started.elapsed())
+        });
+    let abandoned_connections = match tokio::time::timeout(remaining, pool.close()).await {
+        Ok(()) => 0,
+        Err(_) => pool.size(),
+    };
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let drained_in = drain_started
+        .get()
+        .map_or(0, |started: Instant| started.elapsed().as_millis());
+    match abandoned_connections {
+        0 => tracing::info!(
+            drained_in_ms = drained_in,
+            served = requests.completed(),
+            "Shutdown complete, database pool closed"
+        ),
+        _ => tracing::warn!(
+            drained_in_ms = drained_in,
+            served = requests.completed(),
+            abandoned_connections,
+            "Shutdown complete, database connections abandoned"
+        ),
+    }
 
     Ok(())
 }
@@ -1,11 +1,10 @@
-use axum::response::Html;
-use minijinja::render;
+use axum::{extract::State, response::Response};
 
-use crate::view::get_template;
+use crate::{custom::HtmlResponse, error::AppResult, state::AppState};
 
-pub async fn render_student_list() -> Html<String> {
-    let template = get_template("menu").unwrap();
-    let r = render!(template);
-
-    Html(r)
+pub async fn render_student_list(State(state): State<AppState>) -> AppResult<Response> {
+    Ok(state
+        .views
+        .render_template("menu", None::<()>)?
+        .to_html_response())
 }
@@ -0,0 +1,258 @@
+use super::model::OperatingSystem;
+use crate::{
+    custom::{HtmlResponse, JsonError},
+    error::{AppError, AppResult},
+    pagination::{Page, PageQuery},
+    state::AppState,
+    validation::{FieldErrors, TakenBy, Validate},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use axum_extra::extract::{Form, WithRejection};
+use serde::{Deserialize, Serialize};
+
+const NAME_MAX_LENGTH: usize = 50;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OperatingSystemControllerModel {
+    #[serde(default)]
+    name: String,
+}
+
+impl Validate for OperatingSystemControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        errors
+            .required("name", &self.name)
+            .max_length("name", &self.name, NAME_MAX_LENGTH);
+        errors.into_result()
+    }
+}
+
+/// `operating_system` holds the submitted values when the form is rendered again to fix its
+/// `errors`.
+#[derive(Serialize, Default)]
+pub struct CreateOperatingSystemHtmlControllerModel {
+    operating_system: Option<OperatingSystemControllerModel>,
+    errors: FieldErrors,
+    name_taken_by: Option<TakenBy>,
+}
+
+#[derive(Serialize)]
+pub struct EditOperatingSystemHtmlControllerModel {
+    operating_system: OperatingSystem,
+    errors: FieldErrors,
+    name_taken_by: Option<TakenBy>,
+}
+
+/// Operating system other than `operating_system_id` that already uses the name.
+async fn name_taken_by(
+    state: &AppState,
+    name: &str,
+    operating_system_id: Option<&str>,
+) -> AppResult<Option<TakenBy>> {
+    Ok(state
+        .operating_systems
+        .get_by_name(name)
+        .await?
+        .filter(|owner| Some(owner.id.as_str()) != operating_system_id)
+        .map(|owner| TakenBy {
+            entity: "operating-system",
+            id: owner.id,
+            label: owner.name,
+        }))
+}
+
+async fn find_operating_system(
+    state: &AppState,
+    operating_system_id: &str,
+) -> AppResult<OperatingSystem> {
+    state
+        .operating_systems
+        .get_by_id(operating_system_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Operating system does not exists".to_string()))
+}
+
+pub async fn operating_system_list_html(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+) -> AppResult<Response> {
+    let operating_systems = state
+        .operating_systems
+        .list_operating_systems(&page.try_into()?)
+        .await?;
+
+    Ok(state
+        .views
+        .render_template("operating_system/list", operating_systems.into())?
+        .to_html_response())
+}
+
+pub async fn create_operating_system_form_html(
+    State(state): State<AppState>,
+) -> AppResult<Response> {
+    let context = CreateOperatingSystemHtmlControllerModel::default();
+
+    Ok(state
+        .views
+        .render_template("operating_system/create", context.into())?
+        .to_html_response())
+}
+
+pub async fn edit_operating_system_form_html(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+) -> AppResult<Response> {
+    let context = EditOperatingSystemHtmlControllerModel {
+        operating_system: find_operating_system(&state, &operating_system_id).await?,
+        errors: FieldErrors::new(),
+        name_taken_by: None,
+    };
+
+    Ok(state
+        .views
+        .render_template("operating_system/edit", context.into())?
+        .to_html_response())
+}
+
+pub async fn show_operating_system_html(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+) -> AppResult<Response> {
+    let operating_system = find_operating_system(&state, &operating_system_id).await?;
+
+    Ok(state
+        .views
+        .render_template("operating_system/show", operating_system.into())?
+        .to_html_response())
+}
+
+pub async fn create_operating_system(
+    State(state): State<AppState>,
+    Form(operating_system): Form<OperatingSystemControllerModel>,
+) -> AppResult<Response> {
+    let invalid = operating_system.validate().err();
+    let taken_by = name_taken_by(&state, &operating_system.name, None).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        let context = CreateOperatingSystemHtmlControllerModel {
+            operating_system: Some(operating_system),
+            errors: invalid.unwrap_or_default(),
+            name_taken_by: taken_by,
+        };
+        let html = state
+            .views
+            .render_template("operating_system/create", context.into())?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    state.operating_systems.save(&operating_system.name).await?;
+
+    Ok(Redirect::to("/operating-systems").into_response())
+}
+
+pub async fn edit_operating_system(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+    Form(operating_system): Form<OperatingSystemControllerModel>,
+) -> AppResult<Response> {
+    let invalid = operating_system.validate().err();
+    let taken_by =
+        name_taken_by(&state, &operating_system.name, Some(&operating_system_id)).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        let mut submitted = find_operating_system(&state, &operating_system_id).await?;
+        submitted.name = operating_system.name;
+
+        let context = EditOperatingSystemHtmlControllerModel {
+            operating_system: submitted,
+            errors: invalid.unwrap_or_default(),
+            name_taken_by: taken_by,
+        };
+        let html = state
+            .views
+            .render_template("operating_system/edit", context.into())?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    state
+        .operating_systems
+        .edit(&operating_system_id, &operating_system.name)
+        .await?;
+
+    Ok(Redirect::to("/operating-systems").into_response())
+}
+
+pub async fn delete_operating_system(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+) -> AppResult<StatusCode> {
+    state.operating_systems.delete(operating_system_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_operating_systems_json(
+    State(state): State<AppState>,
+    WithRejection(Query(page), _): WithRejection<Query<PageQuery>, JsonError>,
+) -> Result<Json<Page<OperatingSystem>>, JsonError> {
+    Ok(Json(
+        state
+            .operating_systems
+            .list_operating_systems(&page.try_into()?)
+            .await?,
+    ))
+}
+
+pub async fn show_operating_system_json(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+) -> Result<Json<OperatingSystem>, JsonError> {
+    Ok(Json(
+        find_operating_system(&state, &operating_system_id).await?,
+    ))
+}
+
+pub async fn create_operating_system_json(
+    State(state): State<AppState>,
+    WithRejection(Json(operating_system), _): WithRejection<
+        Json<OperatingSystemControllerModel>,
+        JsonError,
+    >,
+) -> Result<impl IntoResponse, JsonError> {
+    operating_system.validate()?;
+    let operating_system = state.operating_systems.save(&operating_system.name).await?;
+
+    Ok((StatusCode::CREATED, Json(operating_system)))
+}
+
+pub async fn update_operating_system_json(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+    WithRejection(Json(operating_system), _): WithRejection<
+        Json<OperatingSystemControllerModel>,
+        JsonError,
+    >,
+) -> Result<Json<OperatingSystem>, JsonError> {
+    operating_system.validate()?;
+    let operating_system = state
+        .operating_systems
+        .edit(&operating_system_id, &operating_system.name)
+        .await?;
+
+    Ok(Json(operating_system))
+}
+
+pub async fn delete_operating_system_json(
+    State(state): State<AppState>,
+    Path(operating_system_id): Path<String>,
+) -> Result<StatusCode, JsonError> {
+    state.operating_systems.delete(operating_system_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
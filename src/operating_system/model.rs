@@ -0,0 +1,28 @@
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+
+/// Operating system a student can pick, students refer to it by its name.
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct OperatingSystem {
+    pub id: String,
+    pub name: String,
+}
+
+impl OperatingSystem {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: Self::generate_id(),
+            name: name.to_string(),
+        }
+    }
+
+    fn generate_id() -> String {
+        static ALPHABET: [char; 35] = [
+            '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
+            'h', 'i', 'j', 'k', 'l', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'w', 'v', 'x', 'y',
+            'z',
+        ];
+
+        nanoid!(10, &ALPHABET)
+    }
+}
@@ -0,0 +1,407 @@
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{
+    error::AppResult,
+    operating_system::model::OperatingSystem,
+    pagination::{unknown_cursor, Page, PageRequest},
+};
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+use tracing::instrument;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn delete(&self, operating_system_id: &str) -> AppResult<()>;
+
+    async fn get_by_id(&self, operating_system_id: &str) -> AppResult<Option<OperatingSystem>>;
+
+    /// The operating system with this name, whatever its case.
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<OperatingSystem>>;
+
+    /// Fails with `BadRequest` when the cursor is not an operating system.
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<OperatingSystem>>;
+
+    /// Every operating system, for the checkboxes of the student forms.
+    async fn list_all(&self) -> AppResult<Vec<OperatingSystem>>;
+
+    async fn insert(&self, operating_system: &OperatingSystem) -> AppResult<OperatingSystem>;
+
+    /// Renames the operating system, `None` when it does not exist.
+    async fn update(
+        &self,
+        operating_system_id: &str,
+        name: &str,
+    ) -> AppResult<Option<OperatingSystem>>;
+
+    /// Whether a student, trashed ones included, uses the operating system.
+    async fn is_used(&self, operating_system_id: &str) -> AppResult<bool>;
+}
+
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.delete"))]
+    async fn delete(&self, operating_system_id: &str) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM operating_system WHERE id = $1
+            "#,
+            operating_system_id
+        )
+        .execute(&self.database)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "operating_system.get_by_id")
+    )]
+    async fn get_by_id(&self, operating_system_id: &str) -> AppResult<Option<OperatingSystem>> {
+        let operating_system = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            SELECT id, name FROM operating_system WHERE id = $1
+            "#,
+            operating_system_id
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(operating_system)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "operating_system.get_by_name")
+    )]
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<OperatingSystem>> {
+        let operating_system = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            SELECT id, name FROM operating_system WHERE lower(name) = lower($1)
+            "#,
+            name
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(operating_system)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.list"))]
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<OperatingSystem>> {
+        // Operating systems are deleted for good, any existing one is a live cursor.
+        if let Some(cursor) = page.anchor() {
+            let known = sqlx::query_scalar!(
+                r#"SELECT EXISTS (SELECT 1 FROM operating_system WHERE id = $1) "known!""#,
+                cursor
+            )
+            .fetch_one(&self.database)
+            .await?;
+
+            if !known {
+                return Err(unknown_cursor("operating system"));
+            }
+        }
+
+        // The previous page ends right before the row of `before`.
+        let page = &match &page.before {
+            Some(before) => {
+                let position = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) "position!" FROM operating_system
+                    WHERE (lower(name), id)
+                        < (SELECT lower(name), id FROM operating_system WHERE id = $1)
+                    "#,
+                    before
+                )
+                .fetch_one(&self.database)
+                .await?;
+
+                page.ending_before(position)
+            }
+            None => page.clone(),
+        };
+
+        let operating_systems = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            SELECT id, name FROM operating_system
+            WHERE $1::text IS NULL
+                OR (lower(name), id) > (SELECT lower(name), id FROM operating_system WHERE id = $1)
+            ORDER BY lower(name), id
+            LIMIT $2 OFFSET $3
+            "#,
+            page.cursor,
+            page.limit(),
+            page.offset(),
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let total = sqlx::query_scalar!(r#"SELECT COUNT(*) "total!" FROM operating_system"#)
+            .fetch_one(&self.database)
+            .await?;
+
+        Ok(Page::new(
+            operating_systems,
+            total,
+            page,
+            |operating_system| &operating_system.id,
+        ))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.list_all"))]
+    async fn list_all(&self) -> AppResult<Vec<OperatingSystem>> {
+        let operating_systems = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            SELECT id, name FROM operating_system ORDER BY lower(name), id
+            "#,
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(operating_systems)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.insert"))]
+    async fn insert(&self, operating_system: &OperatingSystem) -> AppResult<OperatingSystem> {
+        let inserted_operating_system = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            INSERT INTO operating_system (id, name)
+            VALUES ($1, $2)
+            RETURNING id, name
+            "#,
+            operating_system.id,
+            operating_system.name,
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(inserted_operating_system)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.update"))]
+    async fn update(
+        &self,
+        operating_system_id: &str,
+        name: &str,
+    ) -> AppResult<Option<OperatingSystem>> {
+        let updated_operating_system = sqlx::query_as!(
+            OperatingSystem,
+            r#"
+            UPDATE operating_system SET name = $2
+            WHERE id = $1
+            RETURNING id, name
+            "#,
+            operating_system_id,
+            name,
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(updated_operating_system)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "operating_system.is_used"))]
+    async fn is_used(&self, operating_system_id: &str) -> AppResult<bool> {
+        let used = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM student_operating_system WHERE operating_system_id = $1
+            ) "used!"
+            "#,
+            operating_system_id
+        )
+        .fetch_one(&self.database)
+        .await?;
+
+        Ok(used)
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn check_name(tables: &memory::Tables, operating_system_id: &str, name: &str) -> AppResult<()> {
+        if tables
+            .operating_systems
+            .iter()
+            .any(|os| os.id != operating_system_id && os.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(memory::unique_violation("operating_system_name_key"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn delete(&self, operating_system_id: &str) -> AppResult<()> {
+        let mut tables = self.database.write();
+
+        tables
+            .operating_systems
+            .retain(|os| os.id != operating_system_id);
+
+        Ok(())
+    }
+
+    async fn get_by_id(&self, operating_system_id: &str) -> AppResult<Option<OperatingSystem>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .operating_systems
+            .iter()
+            .find(|os| os.id == operating_system_id)
+            .cloned())
+    }
+
+    async fn get_by_name(&self, name: &str) -> AppResult<Option<OperatingSystem>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .operating_systems
+            .iter()
+            .find(|os| os.name.to_lowercase() == name.to_lowercase())
+            .cloned())
+    }
+
+    async fn list(&self, page: &PageRequest) -> AppResult<Page<OperatingSystem>> {
+        if let Some(cursor) = page.anchor() {
+            if !self
+                .database
+                .read()
+                .operating_systems
+                .iter()
+                .any(|os| os.id == *cursor)
+            {
+                return Err(unknown_cursor("operating system"));
+            }
+        }
+
+        let operating_systems = self.list_all().await?;
+
+        Ok(memory::paginate(
+            operating_systems,
+            page,
+            |operating_system| &operating_system.id,
+        ))
+    }
+
+    async fn list_all(&self) -> AppResult<Vec<OperatingSystem>> {
+        let mut operating_systems = self.database.read().operating_systems.clone();
+        operating_systems
+            .sort_by(|a, b| (a.name.to_lowercase(), &a.id).cmp(&(b.name.to_lowercase(), &b.id)));
+
+        Ok(operating_systems)
+    }
+
+    async fn insert(&self, operating_system: &OperatingSystem) -> AppResult<OperatingSystem> {
+        let mut tables = self.database.write();
+
+        if tables
+            .operating_systems
+            .iter()
+            .any(|os| os.id == operating_system.id)
+        {
+            return Err(memory::constraint_violation("operating_system_pkey"));
+        }
+        Self::check_name(&tables, &operating_system.id, &operating_system.name)?;
+        tables.operating_systems.push(operating_system.clone());
+
+        Ok(operating_system.clone())
+    }
+
+    async fn update(
+        &self,
+        operating_system_id: &str,
+        name: &str,
+    ) -> AppResult<Option<OperatingSystem>> {
+        let mut guard = self.database.write();
+        let tables = &mut *guard;
+
+        let Some(position) = tables
+            .operating_systems
+            .iter()
+            .position(|os| os.id == operating_system_id)
+        else {
+            return Ok(None);
+        };
+        Self::check_name(tables, operating_system_id, name)?;
+
+        // Students hold the names, in Postgres they are read through the join table.
+        let old_name = std::mem::replace(
+            &mut tables.operating_systems[position].name,
+            name.to_string(),
+        );
+        let trashed_students =
+            tables
+                .trash
+                .iter_mut()
+                .filter_map(|trashed| match &mut trashed.row {
+                    memory::TrashedRow::Student(student) => Some(student),
+                    _ => None,
+                });
+        for student in tables.students.iter_mut().chain(trashed_students) {
+            for operational_system in student.operational_systems.iter_mut() {
+                if *operational_system == old_name {
+                    *operational_system = name.to_string();
+                }
+            }
+        }
+
+        Ok(Some(tables.operating_systems[position].clone()))
+    }
+
+    async fn is_used(&self, operating_system_id: &str) -> AppResult<bool> {
+        let tables = self.database.read();
+
+        let Some(operating_system) = tables
+            .operating_systems
+            .iter()
+            .find(|os| os.id == operating_system_id)
+        else {
+            return Ok(false);
+        };
+
+        let trashed_students = tables
+            .trash
+            .iter()
+            .filter_map(|trashed| match &trashed.row {
+                memory::TrashedRow::Student(student) => Some(student),
+                _ => None,
+            });
+
+        Ok(tables
+            .students
+            .iter()
+            .chain(trashed_students)
+            .any(|student| student.operational_systems.contains(&operating_system.name)))
+    }
+}
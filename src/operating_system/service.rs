@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+
+use crate::{
+    error::{AppError, AppResult},
+    pagination::{Page, PageRequest},
+};
+
+use super::{model::OperatingSystem, repository::Repository};
+
+pub struct Service {
+    repository: Arc<dyn Repository>,
+}
+
+impl Service {
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Service { repository }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn save(&self, name: &str) -> AppResult<OperatingSystem> {
+        let operating_system = OperatingSystem::new(name);
+        self.repository.insert(&operating_system).await
+    }
+
+    /// Renames the operating system, the students using it show the new name.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn edit(&self, id: &str, name: &str) -> AppResult<OperatingSystem> {
+        self.repository
+            .update(id, name)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Operating system does not exists".to_string()))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete(&self, operating_system_id: String) -> AppResult<()> {
+        if self
+            .repository
+            .get_by_id(&operating_system_id)
+            .await?
+            .is_none()
+        {
+            return Err(AppError::NotFound(
+                "Operating system does not exists".to_string(),
+            ));
+        }
+
+        match self.repository.is_used(&operating_system_id).await? {
+            false => self.repository.delete(&operating_system_id).await,
+            true => Err(AppError::Conflict(
+                "Unable to delete the operating system because a student uses it".to_string(),
+            )),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_id(&self, operating_system_id: &str) -> AppResult<Option<OperatingSystem>> {
+        self.repository.get_by_id(operating_system_id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_name(&self, name: &str) -> AppResult<Option<OperatingSystem>> {
+        self.repository.get_by_name(name).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn list_operating_systems(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<OperatingSystem>> {
+        self.repository.list(page).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn list_all_operating_systems(&self) -> AppResult<Vec<OperatingSystem>> {
+        self.repository.list_all().await
+    }
+}
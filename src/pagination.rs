@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// Raw `?cursor`, `?before`, `?page` and `?per_page` query parameters of the list routes.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    cursor: Option<String>,
+    before: Option<String>,
+    page: Option<u32>,
+    per_page: Option<i64>,
+}
+
+/// Validated page request handed to the repositories.
+///
+/// When a cursor is given the page starts right after that row (keyset pagination), when
+/// `before` is given it ends right before that row, and `page` only numbers the page for
+/// display. Otherwise the page is located by offset.
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub cursor: Option<String>,
+    pub before: Option<String>,
+    pub page: u32,
+    pub per_page: i64,
+    /// Rows skipped once `before` is resolved to the position of its row.
+    skip: Option<i64>,
+}
+
+impl PageRequest {
+    /// One extra row is fetched to know whether there is a next page.
+    pub fn limit(&self) -> i64 {
+        self.per_page + 1
+    }
+
+    pub fn offset(&self) -> i64 {
+        match (&self.cursor, self.skip) {
+            (Some(_), _) => 0,
+            (None, Some(skip)) => skip,
+            (None, None) => i64::from(self.page - 1) * self.per_page,
+        }
+    }
+
+    /// Row the page is anchored to, the one of `cursor` or `before`.
+    pub fn anchor(&self) -> Option<&str> {
+        self.cursor.as_deref().or(self.before.as_deref())
+    }
+
+    /// Resolves `before` once the repository counted the `position` rows listed ahead of its
+    /// row: the page holds the rows right before it, a full first page when there are too few.
+    pub fn ending_before(&self, position: i64) -> PageRequest {
+        let skip = (position - self.per_page).max(0);
+
+        PageRequest {
+            cursor: None,
+            before: None,
+            page: if skip == 0 { 1 } else { self.page },
+            per_page: self.per_page,
+            skip: Some(skip),
+        }
+    }
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        PageRequest {
+            cursor: None,
+            before: None,
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+            skip: None,
+        }
+    }
+}
+
+impl TryFrom<PageQuery> for PageRequest {
+    type Error = AppError;
+
+    fn try_from(query: PageQuery) -> AppResult<Self> {
+        let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(AppError::BadRequest(format!(
+                "per_page must be between 1 and {MAX_PER_PAGE}"
+            )));
+        }
+
+        let page = query.page.unwrap_or(1);
+        if page == 0 {
+            return Err(AppError::BadRequest("page starts at 1".to_string()));
+        }
+
+        let cursor = query.cursor.filter(|cursor| !cursor.is_empty());
+        let before = query.before.filter(|before| !before.is_empty());
+        if cursor.is_some() && before.is_some() {
+            return Err(AppError::BadRequest(
+                "cursor and before cannot be used together".to_string(),
+            ));
+        }
+
+        Ok(PageRequest {
+            cursor,
+            before,
+            page,
+            per_page,
+            skip: None,
+        })
+    }
+}
+
+/// The cursor is not a live row of the list, no keyset page starts after it.
+pub fn unknown_cursor(entity: &str) -> AppError {
+    AppError::BadRequest(format!("cursor is not a live {entity}"))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: u32,
+    pub per_page: i64,
+    pub total_pages: i64,
+    pub next_cursor: Option<String>,
+    /// `?before` of the previous page, the id of the first row shown.
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Builds the page from up to `request.limit()` rows, the extra row only tells that a next
+    /// page exists and the cursor of the next page is the id of the last row shown. A page that
+    /// starts after a cursor or skips rows has a previous page, which ends at its first row.
+    pub fn new(
+        mut rows: Vec<T>,
+        total: i64,
+        request: &PageRequest,
+        cursor_of: impl Fn(&T) -> &str,
+    ) -> Self {
+        let has_next = rows.len() as i64 > request.per_page;
+        rows.truncate(request.per_page as usize);
+
+        let next_cursor = match has_next {
+            true => rows.last().map(|row| cursor_of(row).to_string()),
+            false => None,
+        };
+        let prev_cursor = match request.cursor.is_some() || request.offset() > 0 {
+            true => rows.first().map(|row| cursor_of(row).to_string()),
+            false => None,
+        };
+
+        Page {
+            items: rows,
+            total,
+            page: request.page,
+            per_page: request.per_page,
+            total_pages: (total + request.per_page - 1) / request.per_page,
+            next_cursor,
+            prev_cursor,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+            total_pages: self.total_pages,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+        }
+    }
+}
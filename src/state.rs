@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    avatar, course, health,
+    infra::{db::DatabaseStatus, metrics::Metrics, shutdown::RequestTracker},
+    language, operating_system, student, subject, trash,
+    view::Views,
+};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub students: Arc<student::service::Service>,
+    pub courses: Arc<course::service::Service>,
+    pub subjects: Arc<subject::service::Service>,
+    pub avatars: Arc<avatar::service::Service>,
+    pub operating_systems: Arc<operating_system::service::Service>,
+    pub languages: Arc<language::service::Service>,
+    pub trash: Arc<trash::service::Service>,
+    pub health: Arc<health::service::Service>,
+    pub views: Arc<Views>,
+    pub database: Arc<DatabaseStatus>,
+    pub requests: Arc<RequestTracker>,
+    pub metrics: Arc<Metrics>,
+}
+
+impl AppState {
+    /// Takes one repository per entity, the services get the ones they read from.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        student_repository: Arc<dyn student::repository::Repository>,
+        course_repository: Arc<dyn course::repository::Repository>,
+        subject_repository: Arc<dyn subject::repository::Repository>,
+        avatar_repository: Arc<dyn avatar::repository::Repository>,
+        operating_system_repository: Arc<dyn operating_system::repository::Repository>,
+        language_repository: Arc<dyn language::repository::Repository>,
+        trash_repository: Arc<dyn trash::repository::Repository>,
+        health_repository: Arc<dyn health::repository::Repository>,
+        views: Views,
+    ) -> Self {
+        let database = Arc::new(DatabaseStatus::default());
+
+        AppState {
+            students: Arc::new(student::service::Service::new(
+                student_repository.clone(),
+                course_repository.clone(),
+                operating_system_repository.clone(),
+                language_repository.clone(),
+            )),
+            courses: Arc::new(course::service::Service::new(
+                course_repository.clone(),
+                student_repository.clone(),
+                subject_repository.clone(),
+                avatar_repository.clone(),
+            )),
+            subjects: Arc::new(subject::service::Service::new(
+                subject_repository,
+                course_repository,
+            )),
+            avatars: Arc::new(avatar::service::Service::new(
+                avatar_repository,
+                student_repository,
+            )),
+            operating_systems: Arc::new(operating_system::service::Service::new(
+                operating_system_repository,
+            )),
+            languages: Arc::new(language::service::Service::new(language_repository)),
+            trash: Arc::new(trash::service::Service::new(trash_repository)),
+            health: Arc::new(health::service::Service::new(
+                health_repository,
+                database.clone(),
+            )),
+            views: Arc::new(views),
+            database,
+            requests: Arc::new(RequestTracker::default()),
+            metrics: Arc::new(Metrics::new()),
+        }
+    }
+
+    pub fn postgres(pool: Pool<Postgres>, views: Views) -> Self {
+        AppState::new(
+            Arc::new(student::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(course::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(subject::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(avatar::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(operating_system::repository::PostgresRepository::new(
+                pool.clone(),
+            )),
+            Arc::new(language::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(trash::repository::PostgresRepository::new(pool.clone())),
+            Arc::new(health::repository::PostgresRepository::new(pool)),
+            views,
+        )
+    }
+
+    #[cfg(test)]
+    pub fn in_memory(database: crate::infra::memory::Database) -> Self {
+        let state = AppState::new(
+            Arc::new(student::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(course::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(subject::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(avatar::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(operating_system::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(language::repository::InMemoryRepository::new(
+                database.clone(),
+            )),
+            Arc::new(trash::repository::InMemoryRepository::new(database.clone())),
+            Arc::new(health::repository::InMemoryRepository::new(database)),
+            Views::new("src/view"),
+        );
+        state.database.set_ready();
+        state
+    }
+}
@@ -1,49 +1,234 @@
-use super::model::Student;
-use super::service::SERVICE;
+use super::model::{SortField, Student, StudentFilter, StudentSort};
 use crate::avatar::model::Avatar;
 use crate::course::model::Course;
-use crate::custom::HtmlResponse;
+use crate::custom::{HtmlResponse, JsonError};
+use crate::error::{AppError, AppResult};
+use crate::language::model::Language;
+use crate::pagination::{Page, PageQuery};
+use crate::state::AppState;
+use crate::student::service::GroupBy;
 use crate::subject::model::Subject;
-use crate::view::render_template;
-use crate::{course, student::service::GroupBy};
+use crate::validation::{FieldErrors, TakenBy, Validate};
 use axum::{
-    extract::{Path, Query},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
-use axum_extra::extract::Form;
+use axum_extra::extract::{Form, WithRejection};
 use serde::{Deserialize, Serialize};
 
+const NAME_MAX_LENGTH: usize = 100;
+
+const EMAIL_MAX_LENGTH: usize = 254;
+
 #[derive(Debug, Deserialize)]
 pub struct GroupByQueryParam {
     entity: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// Filters and sort of the student list, serialized back into the pagination and sort links.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct StudentListQueryParam {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    course: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email_domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order: Option<String>,
+}
+
+impl StudentListQueryParam {
+    /// Empty fields of the filter form mean "any".
+    fn normalize(self) -> Self {
+        let non_empty = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        StudentListQueryParam {
+            course: non_empty(self.course),
+            language: non_empty(self.language),
+            os: non_empty(self.os),
+            email_domain: non_empty(self.email_domain)
+                .map(|domain| domain.trim_start_matches('@').to_string()),
+            name: non_empty(self.name),
+            sort: non_empty(self.sort),
+            order: non_empty(self.order),
+        }
+    }
+
+    fn filter(&self) -> StudentFilter {
+        StudentFilter {
+            course_id: self.course.clone(),
+            language: self.language.clone(),
+            operational_system: self.os.clone(),
+            email_domain: self.email_domain.clone(),
+            name: self.name.clone(),
+        }
+    }
+
+    fn sort(&self) -> AppResult<StudentSort> {
+        let field = match self.sort.as_deref() {
+            None | Some("name") => SortField::Name,
+            Some("email") => SortField::Email,
+            Some("course") => SortField::Course,
+            Some("created_at") => SortField::CreatedAt,
+            Some(_) => return Err(AppError::BadRequest("Invalid field to sort".to_string())),
+        };
+
+        let descending = match self.order.as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return Err(AppError::BadRequest("Invalid sort order".to_string())),
+        };
+
+        Ok(StudentSort { field, descending })
+    }
+
+    fn without_sort(&self) -> Self {
+        StudentListQueryParam {
+            sort: None,
+            order: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CreateStudentControllerModel {
+    #[serde(default)]
     first_name: String,
+    #[serde(default)]
     last_name: String,
+    #[serde(default)]
     course: String,
+    #[serde(default)]
     email: String,
+    #[serde(default)]
     language: String,
+    #[serde(default)]
     operational_systems: Vec<String>,
 }
 
+/// Partial update, the fields that are left out keep their current value.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PatchStudentControllerModel {
+    first_name: Option<String>,
+    last_name: Option<String>,
+    course: Option<String>,
+    email: Option<String>,
+    language: Option<String>,
+    operational_systems: Option<Vec<String>>,
+}
+
+fn validate_name(errors: &mut FieldErrors, field: &'static str, name: &str) {
+    errors
+        .required(field, name)
+        .max_length(field, name, NAME_MAX_LENGTH);
+}
+
+fn validate_email(errors: &mut FieldErrors, email: &str) {
+    errors
+        .required("email", email)
+        .max_length("email", email, EMAIL_MAX_LENGTH)
+        .email("email", email);
+}
+
+impl Validate for CreateStudentControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        validate_name(&mut errors, "first_name", &self.first_name);
+        validate_name(&mut errors, "last_name", &self.last_name);
+        validate_email(&mut errors, &self.email);
+        errors
+            .required("course", &self.course)
+            .required("language", &self.language);
+        errors.into_result()
+    }
+}
+
+/// Only the fields that are sent are checked, the others keep their valid current value.
+impl Validate for PatchStudentControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        if let Some(first_name) = &self.first_name {
+            validate_name(&mut errors, "first_name", first_name);
+        }
+        if let Some(last_name) = &self.last_name {
+            validate_name(&mut errors, "last_name", last_name);
+        }
+        if let Some(email) = &self.email {
+            validate_email(&mut errors, email);
+        }
+        if let Some(course) = &self.course {
+            errors.required("course", course);
+        }
+        if let Some(language) = &self.language {
+            errors.required("language", language);
+        }
+        errors.into_result()
+    }
+}
+
 #[derive(Serialize)]
 pub struct ListStudentControllerModel {
     student: Student,
     avatar: Option<Avatar>,
 }
 
+#[derive(Serialize)]
+pub struct ListStudentHtmlControllerModel {
+    students: Page<ListStudentControllerModel>,
+    params: StudentListQueryParam,
+    filters: StudentListQueryParam,
+    courses: Vec<Course>,
+    languages: Vec<Language>,
+    os: Vec<String>,
+}
+
+/// `?course` preselects the course of the form, used by the links of the course page.
+#[derive(Deserialize, Debug, Default)]
+pub struct CreateStudentQueryParam {
+    course: Option<String>,
+}
+
+/// `student` holds the submitted values when the form is rendered again to fix its `errors`.
 #[derive(Serialize)]
 pub struct CreateStudentHtmlControllerModel {
     courses: Vec<Course>,
+    selected_course: Option<String>,
+    student: Option<CreateStudentControllerModel>,
+    languages: Vec<Language>,
+    os: Vec<String>,
+    errors: FieldErrors,
+    email_taken_by: Option<TakenBy>,
+}
+
+#[derive(Serialize)]
+pub struct EditStudentHtmlControllerModel {
+    student: Student,
+    courses: Vec<Course>,
+    languages: Vec<Language>,
     os: Vec<String>,
+    errors: FieldErrors,
+    email_taken_by: Option<TakenBy>,
 }
 
 #[derive(Serialize)]
-pub struct ShowStudentHtmlControllerModel {
+pub struct ShowStudentControllerModel {
     student: Student,
+    /// Catalog entry of the language code kept by the student.
+    language: Option<Language>,
     course: Course,
     subjects: Vec<Subject>,
 }
@@ -54,85 +239,208 @@ pub struct ListStudentGroupByHtmlControllerModel {
     students: Vec<Student>,
 }
 
-pub async fn student_list_html() -> impl IntoResponse {
-    let students_with_avatar = match SERVICE.list_students_with_avatar().await {
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(students_with_avatar) => students_with_avatar,
+pub async fn student_list_html(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+    Query(params): Query<StudentListQueryParam>,
+) -> AppResult<Response> {
+    let params = params.normalize();
+
+    let students_with_avatar = state
+        .students
+        .list_students_with_avatar(&params.filter(), &params.sort()?, &page.try_into()?)
+        .await?;
+
+    let students = students_with_avatar.map(|student| ListStudentControllerModel {
+        student: student.0,
+        avatar: student.1,
+    });
+
+    let context = ListStudentHtmlControllerModel {
+        students,
+        filters: params.without_sort(),
+        params,
+        courses: state.courses.list_all_courses().await?,
+        languages: state.languages.list_all_languages().await?,
+        os: operating_system_names(&state).await?,
     };
 
-    let students_struct: Vec<ListStudentControllerModel> = students_with_avatar
+    Ok(state
+        .views
+        .render_template("student/list", context.into())?
+        .to_html_response())
+}
+
+/// Names offered by the operating system filter and checkboxes.
+async fn operating_system_names(state: &AppState) -> AppResult<Vec<String>> {
+    Ok(state
+        .operating_systems
+        .list_all_operating_systems()
+        .await?
         .into_iter()
-        .map(|student| ListStudentControllerModel {
-            student: student.0,
-            avatar: student.1,
-        })
-        .collect();
+        .map(|operating_system| operating_system.name)
+        .collect())
+}
 
-    render_template("student/list", students_struct.into()).to_html_response()
+/// Student other than `student_id` that already uses the email.
+async fn email_taken_by(
+    state: &AppState,
+    email: &str,
+    student_id: Option<&str>,
+) -> AppResult<Option<TakenBy>> {
+    Ok(state
+        .students
+        .get_by_email(email)
+        .await?
+        .filter(|owner| Some(owner.id.as_str()) != student_id)
+        .map(|owner| TakenBy {
+            entity: "student",
+            label: format!("{} {}", owner.first_name, owner.last_name),
+            id: owner.id,
+        }))
 }
 
-pub async fn create_student_form_html() -> impl IntoResponse {
-    match course::service::SERVICE.list_courses().await {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(courses) => {
-            let os = vec!["OSX", "Windows", "Linux"];
+async fn render_create_form(
+    state: &AppState,
+    selected_course: Option<String>,
+    student: Option<CreateStudentControllerModel>,
+    errors: FieldErrors,
+    email_taken_by: Option<TakenBy>,
+) -> AppResult<String> {
+    let context = CreateStudentHtmlControllerModel {
+        courses: state.courses.list_all_courses().await?,
+        selected_course,
+        student,
+        languages: state.languages.list_all_languages().await?,
+        os: operating_system_names(state).await?,
+        errors,
+        email_taken_by,
+    };
 
-            let context = CreateStudentHtmlControllerModel {
-                courses,
-                os: os.into_iter().map(String::from).collect(),
-            };
+    state
+        .views
+        .render_template("student/create", context.into())
+}
 
-            render_template("student/create", context.into()).to_html_response()
-        }
-    }
+pub async fn create_student_form_html(
+    State(state): State<AppState>,
+    Query(query): Query<CreateStudentQueryParam>,
+) -> AppResult<Response> {
+    Ok(
+        render_create_form(&state, query.course, None, FieldErrors::new(), None)
+            .await?
+            .to_html_response(),
+    )
 }
 
-pub async fn show_student_html(Path(student_id): Path<String>) -> impl IntoResponse {
-    let student = match SERVICE
-        .get_student_with_course_and_subjects(&student_id)
-        .await
-    {
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(student) => student,
+async fn find_student(state: &AppState, student_id: &str) -> AppResult<Student> {
+    state
+        .students
+        .get_by_id(student_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Student does not exists".to_string()))
+}
+
+async fn render_edit_form(
+    state: &AppState,
+    student: Student,
+    errors: FieldErrors,
+    email_taken_by: Option<TakenBy>,
+) -> AppResult<String> {
+    let context = EditStudentHtmlControllerModel {
+        student,
+        courses: state.courses.list_all_courses().await?,
+        languages: state.languages.list_all_languages().await?,
+        os: operating_system_names(state).await?,
+        errors,
+        email_taken_by,
     };
 
-    let context = ShowStudentHtmlControllerModel {
+    state.views.render_template("student/edit", context.into())
+}
+
+pub async fn edit_student_form_html(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+) -> AppResult<Response> {
+    let student = find_student(&state, &student_id).await?;
+
+    Ok(render_edit_form(&state, student, FieldErrors::new(), None)
+        .await?
+        .to_html_response())
+}
+
+pub async fn show_student_html(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+) -> AppResult<Response> {
+    let student = state
+        .students
+        .get_student_with_course_and_subjects(&student_id)
+        .await?;
+
+    let context = ShowStudentControllerModel {
+        language: state.languages.get_by_code(&student.0.language).await?,
         student: student.0,
         course: student.1,
         subjects: student.2,
     };
 
-    render_template("student/show", context.into()).to_html_response()
+    Ok(state
+        .views
+        .render_template("student/show", context.into())?
+        .to_html_response())
 }
 
-pub async fn list_student_group_by_html(Query(q): Query<GroupByQueryParam>) -> impl IntoResponse {
-    let entity_enum = match q.entity.as_str() {
-        "course" => GroupBy::COURSE,
-        "language" => GroupBy::LANGUAGE,
-        "os" => GroupBy::OS,
-        _ => return (StatusCode::BAD_REQUEST, "Invalid entity to group").into_response(),
-    };
-
-    match SERVICE.list_group_by(&entity_enum).await {
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
-        Ok(entities) => {
-            let context: Vec<ListStudentGroupByHtmlControllerModel> = entities
-                .into_iter()
-                .map(|entity| ListStudentGroupByHtmlControllerModel {
-                    name: entity.0,
-                    students: entity.1,
-                })
-                .collect();
-
-            render_template("student/group-by", context.into()).to_html_response()
-        }
+fn parse_group_by(entity: &str) -> AppResult<GroupBy> {
+    match entity {
+        "course" => Ok(GroupBy::COURSE),
+        "language" => Ok(GroupBy::LANGUAGE),
+        "os" => Ok(GroupBy::OS),
+        _ => Err(AppError::BadRequest("Invalid entity to group".to_string())),
     }
 }
 
+pub async fn list_student_group_by_html(
+    State(state): State<AppState>,
+    Query(q): Query<GroupByQueryParam>,
+) -> AppResult<Response> {
+    let entity_enum = parse_group_by(&q.entity)?;
+
+    let context: Vec<ListStudentGroupByHtmlControllerModel> = state
+        .students
+        .list_group_by(&entity_enum)
+        .await?
+        .into_iter()
+        .map(|entity| ListStudentGroupByHtmlControllerModel {
+            name: entity.0,
+            students: entity.1,
+        })
+        .collect();
+
+    Ok(state
+        .views
+        .render_template("student/group-by", context.into())?
+        .to_html_response())
+}
+
 pub async fn create_student(
+    State(state): State<AppState>,
     Form(student): Form<CreateStudentControllerModel>,
-) -> impl IntoResponse {
-    match SERVICE
+) -> AppResult<Response> {
+    let invalid = student.validate().err();
+    let taken_by = email_taken_by(&state, &student.email, None).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        let selected_course = Some(student.course.clone());
+        let errors = invalid.unwrap_or_default();
+        let html =
+            render_create_form(&state, selected_course, Some(student), errors, taken_by).await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    let saved = state
+        .students
         .save(
             &student.first_name,
             &student.last_name,
@@ -141,16 +449,174 @@ pub async fn create_student(
             &student.email,
             student.operational_systems.iter().collect(),
         )
-        .await
-    {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(_) => Redirect::to("/students").into_response(),
+        .await;
+
+    // The operating systems are checked by the service, against the ones in the table.
+    match saved {
+        Err(AppError::InvalidFields(errors)) => {
+            let selected_course = Some(student.course.clone());
+            let html =
+                render_create_form(&state, selected_course, Some(student), errors, None).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
+        }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to("/students").into_response()),
     }
 }
 
-pub async fn delete_student(Path(student_id): Path<String>) -> impl IntoResponse {
-    match SERVICE.delete(student_id).await {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(_) => StatusCode::OK.into_response(),
+pub async fn edit_student(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+    Form(payload): Form<CreateStudentControllerModel>,
+) -> AppResult<Response> {
+    let invalid = payload.validate().err();
+    let student = Student::new_with_id(
+        &student_id,
+        &payload.first_name,
+        &payload.last_name,
+        &payload.course,
+        &payload.language,
+        &payload.email,
+        payload.operational_systems.iter().collect(),
+    );
+
+    let taken_by = email_taken_by(&state, &payload.email, Some(&student_id)).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        find_student(&state, &student_id).await?;
+        let errors = invalid.unwrap_or_default();
+        let html = render_edit_form(&state, student, errors, taken_by).await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
     }
+
+    match state.students.update(student.clone()).await {
+        Err(AppError::InvalidFields(errors)) => {
+            let html = render_edit_form(&state, student, errors, None).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
+        }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to(&format!("/student/{student_id}")).into_response()),
+    }
+}
+
+pub async fn delete_student(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+) -> AppResult<StatusCode> {
+    state.students.delete(student_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_students_json(
+    State(state): State<AppState>,
+    WithRejection(Query(page), _): WithRejection<Query<PageQuery>, JsonError>,
+    WithRejection(Query(params), _): WithRejection<Query<StudentListQueryParam>, JsonError>,
+) -> Result<Json<Page<ListStudentControllerModel>>, JsonError> {
+    let params = params.normalize();
+
+    let students = state
+        .students
+        .list_students_with_avatar(&params.filter(), &params.sort()?, &page.try_into()?)
+        .await?
+        .map(|(student, avatar)| ListStudentControllerModel { student, avatar });
+
+    Ok(Json(students))
+}
+
+pub async fn show_student_json(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+) -> Result<Json<ShowStudentControllerModel>, JsonError> {
+    let (student, course, subjects) = state
+        .students
+        .get_student_with_course_and_subjects(&student_id)
+        .await?;
+
+    Ok(Json(ShowStudentControllerModel {
+        language: state.languages.get_by_code(&student.language).await?,
+        student,
+        course,
+        subjects,
+    }))
+}
+
+pub async fn create_student_json(
+    State(state): State<AppState>,
+    WithRejection(Json(student), _): WithRejection<Json<CreateStudentControllerModel>, JsonError>,
+) -> Result<impl IntoResponse, JsonError> {
+    student.validate()?;
+    let student = state
+        .students
+        .save(
+            &student.first_name,
+            &student.last_name,
+            &student.course,
+            &student.language,
+            &student.email,
+            student.operational_systems.iter().collect(),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(student)))
+}
+
+pub async fn update_student_json(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateStudentControllerModel>, JsonError>,
+) -> Result<Json<Student>, JsonError> {
+    payload.validate()?;
+    let student = Student::new_with_id(
+        &student_id,
+        &payload.first_name,
+        &payload.last_name,
+        &payload.course,
+        &payload.language,
+        &payload.email,
+        payload.operational_systems.iter().collect(),
+    );
+
+    Ok(Json(state.students.update(student).await?))
+}
+
+pub async fn patch_student_json(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+    WithRejection(Json(payload), _): WithRejection<Json<PatchStudentControllerModel>, JsonError>,
+) -> Result<Json<Student>, JsonError> {
+    payload.validate()?;
+    let mut student = find_student(&state, &student_id).await?;
+
+    if let Some(first_name) = payload.first_name {
+        student.first_name = first_name;
+    }
+    if let Some(last_name) = payload.last_name {
+        student.last_name = last_name;
+    }
+    if let Some(course) = payload.course {
+        student.course_id = course;
+    }
+    if let Some(email) = payload.email {
+        student.email = email;
+    }
+    if let Some(language) = payload.language {
+        student.language = language;
+    }
+    if let Some(operational_systems) = payload.operational_systems {
+        student.operational_systems = operational_systems;
+    }
+
+    Ok(Json(state.students.update(student).await?))
+}
+
+pub async fn delete_student_json(
+    State(state): State<AppState>,
+    Path(student_id): Path<String>,
+) -> Result<StatusCode, JsonError> {
+    state.students.delete(student_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -35,6 +35,28 @@ impl Student {
         }
     }
 
+    pub fn new_with_id(
+        id: &str,
+        first_name: &str,
+        last_name: &str,
+        course_id: &str,
+        language: &str,
+        email: &str,
+        operational_systems: Vec<&String>,
+    ) -> Self {
+        Student {
+            id: id.to_string(),
+            ..Student::new(
+                first_name,
+                last_name,
+                course_id,
+                language,
+                email,
+                operational_systems,
+            )
+        }
+    }
+
     fn generate_id() -> String {
         static ALPHABET: [char; 35] = [
             '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
@@ -73,3 +95,29 @@ impl Student {
         &self.operational_systems
     }
 }
+
+/// Filters of the student list, every filter that is set must match.
+#[derive(Debug, Default, Clone)]
+pub struct StudentFilter {
+    pub course_id: Option<String>,
+    pub language: Option<String>,
+    pub operational_system: Option<String>,
+    pub email_domain: Option<String>,
+    /// Substring of the full name, case insensitive.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SortField {
+    #[default]
+    Name,
+    Email,
+    Course,
+    CreatedAt,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StudentSort {
+    pub field: SortField,
+    pub descending: bool,
+}
@@ -1,29 +1,149 @@
-use std::{error::Error, vec};
+use std::vec;
 
-use sqlx::{Pool, Postgres};
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres, QueryBuilder, Row, Transaction};
+use tracing::instrument;
 
-use super::model::Student;
-use crate::{avatar::model::Avatar, course::model::Course, infra, subject::model::Subject};
+use super::model::{SortField, Student, StudentFilter, StudentSort};
+#[cfg(test)]
+use crate::infra::memory;
+use crate::{
+    avatar::model::Avatar,
+    course::model::Course,
+    error::{AppError, AppResult},
+    pagination::{unknown_cursor, Page, PageRequest},
+    subject::model::Subject,
+};
 
-pub struct Repository {
-    database: &'static Pool<Postgres>,
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn insert(&self, student: Student) -> AppResult<Student>;
+
+    /// Fails with `NotFound` when there is no student with this id.
+    async fn update(&self, student: Student) -> AppResult<Student>;
+
+    async fn list_students_by_course_id(&self, course_id: &str) -> AppResult<Vec<Student>>;
+
+    async fn list_students_group_by_course(&self) -> AppResult<Vec<(String, Vec<Student>)>>;
+
+    /// Groups on the language code, each group is named after the language in the catalog.
+    async fn list_students_group_by_language(&self) -> AppResult<Vec<(String, Vec<Student>)>>;
+
+    async fn list_students_group_by_os(&self) -> AppResult<Vec<(String, Vec<Student>)>>;
+
+    /// Moves the student to the trash together with its avatar.
+    async fn delete(&self, student_id: &str) -> AppResult<()>;
+
+    async fn get_by_id(&self, student_id: &str) -> AppResult<Option<Student>>;
+
+    /// The live student with this email, whatever its case.
+    async fn get_by_email(&self, email: &str) -> AppResult<Option<Student>>;
+
+    async fn list_student_that_doesnt_have_avatar(&self) -> AppResult<Vec<Student>>;
+
+    /// Fails with `BadRequest` when the cursor is not a live student.
+    async fn list_student_with_avatar(
+        &self,
+        filter: &StudentFilter,
+        sort: &StudentSort,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Student, Option<Avatar>)>>;
+
+    async fn get_student_with_course_and_subjects(
+        &self,
+        student_id: &str,
+    ) -> AppResult<(Student, Course, Vec<Subject>)>;
 }
 
-impl Repository {
-    pub fn new() -> Self {
-        Repository {
-            database: infra::db::DB_POOL.get().expect("Unable to get DB_POOL"),
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
+    }
+
+    /// Sort key of the student list, always ending with the id so the order is total.
+    fn sort_columns(field: SortField) -> &'static str {
+        match field {
+            SortField::Name => "s.first_name, s.last_name, s.id",
+            SortField::Email => "s.email, s.id",
+            SortField::Course => "c.name, s.first_name, s.last_name, s.id",
+            SortField::CreatedAt => "s.created_at, s.id",
         }
     }
 
-    pub async fn save(&self, student: Student) -> Result<Student, Box<dyn Error>> {
-        let updated_student = sqlx::query_as!(
-            Student,
+    /// Links the student to its operating systems, which the service already checked by name.
+    async fn link_operating_systems(
+        tx: &mut Transaction<'_, Postgres>,
+        student: &Student,
+    ) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO student_operating_system (student_id, operating_system_id)
+            SELECT $1, id FROM operating_system WHERE name = ANY($2)
+            "#,
+            student.get_id(),
+            student.get_operational_systems(),
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filter: &StudentFilter) {
+        query.push(" where s.deleted_at is null");
+
+        if let Some(course_id) = &filter.course_id {
+            query
+                .push(" and s.course_id = ")
+                .push_bind(course_id.clone());
+        }
+
+        if let Some(language) = &filter.language {
+            query.push(" and s.language = ").push_bind(language.clone());
+        }
+
+        if let Some(operational_system) = &filter.operational_system {
+            query
+                .push(" and ")
+                .push_bind(operational_system.clone())
+                .push(" = any(s.operational_systems)");
+        }
+
+        if let Some(domain) = &filter.email_domain {
+            query
+                .push(" and lower(split_part(s.email, '@', 2)) = lower(")
+                .push_bind(domain.clone())
+                .push(")");
+        }
+
+        if let Some(name) = &filter.name {
+            // Wildcards typed by the user are matched literally.
+            let name = name
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+
+            query
+                .push(" and (s.first_name || ' ' || s.last_name) ilike ")
+                .push_bind(format!("%{name}%"));
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "student.insert"))]
+    async fn insert(&self, student: Student) -> AppResult<Student> {
+        let mut tx = self.database.begin().await?;
+
+        sqlx::query!(
             r#"
-            INSERT INTO student (id, first_name, last_name, course_id, language, email, operational_systems)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            ON CONFLICT (id) DO UPDATE SET first_name=excluded.first_name, last_name=excluded.last_name, course_id=excluded.course_id, language=excluded.language, email=excluded.email, operational_systems=excluded.operational_systems
-            RETURNING id, first_name, last_name, course_id, language, email, operational_systems
+            INSERT INTO student (id, first_name, last_name, course_id, language, email)
+            VALUES ($1, $2, $3, $4, $5, $6)
             "#,
             student.get_id(),
             student.get_first_name(),
@@ -31,35 +151,91 @@ impl Repository {
             student.get_course(),
             student.get_language(),
             student.get_email(),
-            student.get_operational_systems()
-        ).fetch_one(self.database).await?;
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        PostgresRepository::link_operating_systems(&mut tx, &student).await?;
+        tx.commit().await?;
 
-        Ok(updated_student)
+        Ok(student)
     }
 
-    pub async fn list_students_by_course_id(
-        &self,
-        course_id: &String,
-    ) -> Result<Vec<Student>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "student.update"))]
+    async fn update(&self, student: Student) -> AppResult<Student> {
+        let mut tx = self.database.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE student
+            SET first_name = $2, last_name = $3, course_id = $4, language = $5, email = $6
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            student.get_id(),
+            student.get_first_name(),
+            student.get_last_name(),
+            student.get_course(),
+            student.get_language(),
+            student.get_email(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Student does not exists".to_string()));
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM student_operating_system WHERE student_id = $1
+            "#,
+            student.get_id(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        PostgresRepository::link_operating_systems(&mut tx, &student).await?;
+        tx.commit().await?;
+
+        Ok(student)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_students_by_course_id")
+    )]
+    async fn list_students_by_course_id(&self, course_id: &str) -> AppResult<Vec<Student>> {
         let students = sqlx::query_as!(
             Student,
             r#"
-            SELECT s.id, first_name, last_name, course_id, language, email, operational_systems
-            FROM student s
+            SELECT
+                s.id "id!",
+                first_name "first_name!",
+                last_name "last_name!",
+                course_id "course_id!",
+                language "language!",
+                email "email!",
+                operational_systems "operational_systems!"
+            FROM student_view s
             INNER JOIN course c on c.id = s.course_id
-            WHERE c.id = $1
+            WHERE c.id = $1 AND s.deleted_at IS NULL
+            ORDER BY first_name, last_name, s.id
             "#,
             course_id
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
         Ok(students)
     }
 
-    pub async fn list_students_group_by_course(
-        &self,
-    ) -> Result<Vec<(String, Vec<Student>)>, Box<dyn Error>> {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_students_group_by_course")
+    )]
+    async fn list_students_group_by_course(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
         let rows = sqlx::query!(
             r#"
             select
@@ -67,13 +243,16 @@ impl Repository {
                 JSON_agg(s.*) "students"
             from
                 course c
-            inner join student s on
+            inner join student_view s on
                 s.course_id = c.id
+                and s.deleted_at is null
+            where
+                c.deleted_at is null
             group by
                 c.id
             "#,
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
         let courses_with_students: Vec<(String, Vec<Student>)> = rows
@@ -92,21 +271,29 @@ impl Repository {
         Ok(courses_with_students)
     }
 
-    pub async fn list_students_group_by_language(
-        &self,
-    ) -> Result<Vec<(String, Vec<Student>)>, Box<dyn Error>> {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_students_group_by_language")
+    )]
+    async fn list_students_group_by_language(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
         let rows = sqlx::query!(
             r#"
             select
-                s."language",
-                json_agg(s.*) "students"
+                l.name,
+                json_agg(s.* order by s.first_name, s.last_name, s.id) "students"
             from
-                student s
+                language l
+            inner join student_view s on
+                s."language" = l.code
+                and s.deleted_at is null
             group by
-                s."language"
+                l.code
+            order by
+                lower(l.name)
             "#,
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
         let languages_with_students: Vec<(String, Vec<Student>)> = rows
@@ -118,29 +305,38 @@ impl Repository {
                 }
                 .unwrap_or(vec![]);
 
-                (row.language, students)
+                (row.name, students)
             })
             .collect();
 
         Ok(languages_with_students)
     }
 
-    pub async fn list_students_group_by_os(
-        &self,
-    ) -> Result<Vec<(String, Vec<Student>)>, Box<dyn Error>> {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_students_group_by_os")
+    )]
+    async fn list_students_group_by_os(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
         let rows = sqlx::query!(
             r#"
             select
-                os "os",
-                JSON_agg(s.*) "students"
+                os.name,
+                json_agg(s.* order by s.first_name, s.last_name, s.id) "students"
             from
-                student s,
-                unnest(s.operational_systems) "os"
+                operating_system os
+            inner join student_operating_system so on
+                so.operating_system_id = os.id
+            inner join student_view s on
+                s.id = so.student_id
+                and s.deleted_at is null
             group by
-                os
+                os.id
+            order by
+                lower(os.name)
             "#,
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
         let os_with_students: Vec<(String, Vec<Student>)> = rows
@@ -152,131 +348,309 @@ impl Repository {
                 }
                 .unwrap_or(vec![]);
 
-                (row.os.unwrap_or("unknow".to_string()), students)
+                (row.name, students)
             })
             .collect();
 
         Ok(os_with_students)
     }
 
-    pub async fn delete(&self, student_id: String) -> Result<(), Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "student.delete"))]
+    async fn delete(&self, student_id: &str) -> AppResult<()> {
+        let mut tx = self.database.begin().await?;
+
+        // CURRENT_TIMESTAMP is the start of the transaction, a restore brings back the avatar
+        // trashed at the same time as the student.
         sqlx::query!(
             r#"
-            DELETE FROM student WHERE id = $1
+            UPDATE avatar SET deleted_at = CURRENT_TIMESTAMP
+            WHERE student_id = $1 AND deleted_at IS NULL
             "#,
             student_id
         )
-        .execute(self.database)
+        .execute(&mut *tx)
         .await?;
 
+        sqlx::query!(
+            r#"
+            UPDATE student SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            student_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
         Ok(())
     }
 
-    pub async fn get_by_id(&self, student_id: &str) -> Result<Option<Student>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "student.get_by_id"))]
+    async fn get_by_id(&self, student_id: &str) -> AppResult<Option<Student>> {
         let student = sqlx::query_as!(
             Student,
             r#"
-            SELECT id, first_name, last_name, course_id, language, email, operational_systems
-            FROM student
-            WHERE id = $1
+            SELECT
+                id "id!",
+                first_name "first_name!",
+                last_name "last_name!",
+                course_id "course_id!",
+                language "language!",
+                email "email!",
+                operational_systems "operational_systems!"
+            FROM student_view
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
             student_id
         )
-        .fetch_optional(self.database)
+        .fetch_optional(&self.database)
         .await?;
 
         Ok(student)
     }
 
-    pub async fn list_student_that_doesnt_have_avatar(
-        &self,
-    ) -> Result<Vec<Student>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all, fields(query = "student.get_by_email"))]
+    async fn get_by_email(&self, email: &str) -> AppResult<Option<Student>> {
+        let student = sqlx::query_as!(
+            Student,
+            r#"
+            SELECT
+                id "id!",
+                first_name "first_name!",
+                last_name "last_name!",
+                course_id "course_id!",
+                language "language!",
+                email "email!",
+                operational_systems "operational_systems!"
+            FROM student_view
+            WHERE lower(email) = lower($1) AND deleted_at IS NULL
+            "#,
+            email
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(student)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_student_that_doesnt_have_avatar")
+    )]
+    async fn list_student_that_doesnt_have_avatar(&self) -> AppResult<Vec<Student>> {
         let students = sqlx::query_as!(
             Student,
             r#"
             SELECT
-                s.id,
-                first_name,
-                last_name,
-                course_id,
-                language,
-                email,
-                operational_systems
+                s.id "id!",
+                first_name "first_name!",
+                last_name "last_name!",
+                course_id "course_id!",
+                language "language!",
+                email "email!",
+                operational_systems "operational_systems!"
             FROM
-                student s
+                student_view s
             LEFT JOIN avatar a ON
                 a.student_id = s.id
+                AND a.deleted_at IS NULL
             WHERE
                 a.student_id IS NULL
+                AND s.deleted_at IS NULL
             "#
         )
-        .fetch_all(self.database)
+        .fetch_all(&self.database)
         .await?;
 
         Ok(students)
     }
 
-    pub async fn list_student_with_avatar(
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.list_student_with_avatar")
+    )]
+    async fn list_student_with_avatar(
         &self,
-    ) -> Result<Vec<(Student, Option<Avatar>)>, Box<dyn Error>> {
-        let rows = sqlx::query!(
+        filter: &StudentFilter,
+        sort: &StudentSort,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Student, Option<Avatar>)>> {
+        if let Some(cursor) = page.anchor() {
+            let known = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM student s
+                    INNER JOIN course c ON c.id = s.course_id AND c.deleted_at IS NULL
+                    WHERE s.id = $1 AND s.deleted_at IS NULL
+                ) "known!"
+                "#,
+                cursor
+            )
+            .fetch_one(&self.database)
+            .await?;
+
+            if !known {
+                return Err(unknown_cursor("student"));
+            }
+        }
+
+        let columns = PostgresRepository::sort_columns(sort.field);
+        let (direction, comparison, preceding) = match sort.descending {
+            true => ("DESC", "<", ">"),
+            false => ("ASC", ">", "<"),
+        };
+
+        // The previous page ends right before the row of `before`, among the filtered students.
+        let page = &match &page.before {
+            Some(before) => {
+                let mut position = QueryBuilder::new(
+                    r#"
+                    select
+                        count(*)
+                    from
+                        student_view s
+                    inner join course c on
+                        c.id = s.course_id
+                        and c.deleted_at is null
+                    "#,
+                );
+                PostgresRepository::push_filters(&mut position, filter);
+                position
+                    .push(format!(
+                        " and ({columns}) {preceding} (select {columns} from student s \
+                        inner join course c on c.id = s.course_id \
+                        where s.id = "
+                    ))
+                    .push_bind(before.clone())
+                    .push(")");
+
+                page.ending_before(
+                    position
+                        .build_query_scalar()
+                        .fetch_one(&self.database)
+                        .await?,
+                )
+            }
+            None => page.clone(),
+        };
+
+        let mut query = QueryBuilder::new(
             r#"
             select
                 row_to_json(s.*) "student",
                 row_to_json(a.*) "avatar"
             from
-                student s
+                student_view s
+            inner join course c on
+                c.id = s.course_id
+                and c.deleted_at is null
             left join avatar a on
                 a.student_id = s.id
-            "#
-        )
-        .fetch_all(self.database)
-        .await?;
+                and a.deleted_at is null
+            "#,
+        );
+        PostgresRepository::push_filters(&mut query, filter);
+
+        if let Some(cursor) = &page.cursor {
+            query
+                .push(format!(
+                    " and ({columns}) {comparison} (select {columns} from student s \
+                    inner join course c on c.id = s.course_id and c.deleted_at is null \
+                    where s.deleted_at is null and s.id = "
+                ))
+                .push_bind(cursor.clone())
+                .push(")");
+        }
+
+        let order: Vec<_> = columns
+            .split(", ")
+            .map(|column| format!("{column} {direction}"))
+            .collect();
+        query
+            .push(format!(" order by {}", order.join(", ")))
+            .push(" limit ")
+            .push_bind(page.limit())
+            .push(" offset ")
+            .push_bind(page.offset());
+
+        let rows = query.build().fetch_all(&self.database).await?;
+
+        let mut count = QueryBuilder::new(
+            r#"
+            select
+                count(*)
+            from
+                student_view s
+            inner join course c on
+                c.id = s.course_id
+                and c.deleted_at is null
+            "#,
+        );
+        PostgresRepository::push_filters(&mut count, filter);
+
+        let total: i64 = count.build_query_scalar().fetch_one(&self.database).await?;
 
         let students_with_avatar: Vec<_> = rows
             .into_iter()
             .filter_map(|row| {
-                row.student.and_then(|student| {
-                    let student: Option<Student> = serde_json::from_value(student).ok();
-                    let avatar: Option<Avatar> = row
-                        .avatar
-                        .and_then(|avatar| serde_json::from_value(avatar).ok());
-
-                    student.map(|student| (student, avatar))
-                })
+                let student: Option<Student> = row
+                    .get::<Option<serde_json::Value>, _>("student")
+                    .and_then(|student| serde_json::from_value(student).ok());
+                let avatar: Option<Avatar> = row
+                    .get::<Option<serde_json::Value>, _>("avatar")
+                    .and_then(|avatar| serde_json::from_value(avatar).ok());
+
+                student.map(|student| (student, avatar))
             })
             .collect();
 
-        Ok(students_with_avatar)
+        Ok(Page::new(
+            students_with_avatar,
+            total,
+            page,
+            |(student, _)| &student.id,
+        ))
     }
 
-    pub async fn get_student_with_course_and_subjects(
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "student.get_student_with_course_and_subjects")
+    )]
+    async fn get_student_with_course_and_subjects(
         &self,
         student_id: &str,
-    ) -> Result<(Student, Course, Vec<Subject>), Box<dyn Error>> {
+    ) -> AppResult<(Student, Course, Vec<Subject>)> {
         let row = sqlx::query!(
             r#"
             select
                 row_to_json(s.*) "student",
                 row_to_json(c.*) "course",
-                json_agg(s2.*) "subjects"
+                (
+                    select
+                        coalesce(json_agg(s2.*), '[]')
+                    from
+                        subject_course sc
+                    inner join subject s2 on
+                        s2.id = sc.subject_id
+                        and s2.deleted_at is null
+                    where
+                        sc.course_id = c.id
+                ) "subjects"
             from
-                student s
+                student_view s
             inner join course c on
                 c.id = s.course_id
-            left join subject_course sc on
-                sc.course_id = c.id
-            left join subject s2 on
-                s2.id = sc.subject_id
-            WHERE s.id = $1
-            group by
-                s.id,
-                c.id
+            WHERE s.id = $1 AND s.deleted_at IS NULL
             "#,
             student_id
         )
-        .fetch_one(self.database)
-        .await?;
+        .fetch_optional(&self.database)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Student does not exists".to_string()))?;
 
         match (row.student, row.course) {
             (Some(student), Some(course)) => {
@@ -289,7 +663,323 @@ impl Repository {
 
                 Ok((student, course, subjects))
             }
-            _ => Err("Student or course is None and can't be".into()),
+            _ => Err(AppError::Internal(
+                "Student or course is None and can't be".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn check_email(tables: &memory::Tables, student: &Student) -> AppResult<()> {
+        if tables
+            .students
+            .iter()
+            .any(|s| s.id != student.id && s.email.to_lowercase() == student.email.to_lowercase())
+        {
+            return Err(memory::unique_violation("student_email_key"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn insert(&self, student: Student) -> AppResult<Student> {
+        let mut tables = self.database.write();
+
+        if !tables.courses.iter().any(|c| c.id == student.course_id) {
+            return Err(memory::constraint_violation("student_course_id_fkey"));
+        }
+        if !tables.languages.iter().any(|l| l.code == student.language) {
+            return Err(memory::constraint_violation("student_language_fkey"));
         }
+
+        if tables.students.iter().any(|s| s.id == student.id) {
+            return Err(memory::constraint_violation("student_pkey"));
+        }
+        Self::check_email(&tables, &student)?;
+
+        tables.students.push(student.clone());
+
+        Ok(student)
+    }
+
+    async fn update(&self, student: Student) -> AppResult<Student> {
+        let mut tables = self.database.write();
+
+        if !tables.courses.iter().any(|c| c.id == student.course_id) {
+            return Err(memory::constraint_violation("student_course_id_fkey"));
+        }
+        if !tables.languages.iter().any(|l| l.code == student.language) {
+            return Err(memory::constraint_violation("student_language_fkey"));
+        }
+        if tables.students.iter().any(|s| s.id == student.id) {
+            Self::check_email(&tables, &student)?;
+        }
+
+        let existing = tables
+            .students
+            .iter_mut()
+            .find(|s| s.id == student.id)
+            .ok_or_else(|| AppError::NotFound("Student does not exists".to_string()))?;
+        *existing = student.clone();
+
+        Ok(student)
+    }
+
+    async fn list_students_by_course_id(&self, course_id: &str) -> AppResult<Vec<Student>> {
+        let tables = self.database.read();
+
+        let mut students: Vec<Student> = tables
+            .students
+            .iter()
+            .filter(|s| s.course_id == course_id)
+            .cloned()
+            .collect();
+        students.sort_by(|a, b| {
+            (&a.first_name, &a.last_name, &a.id).cmp(&(&b.first_name, &b.last_name, &b.id))
+        });
+
+        Ok(students)
+    }
+
+    async fn list_students_group_by_course(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .courses
+            .iter()
+            .map(|course| {
+                let students: Vec<Student> = tables
+                    .students
+                    .iter()
+                    .filter(|s| s.course_id == course.id)
+                    .cloned()
+                    .collect();
+
+                (course.name.clone(), students)
+            })
+            .filter(|(_, students)| !students.is_empty())
+            .collect())
+    }
+
+    async fn list_students_group_by_language(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
+        let tables = self.database.read();
+
+        let mut languages = tables.languages.clone();
+        languages.sort_by_key(|l| l.name.to_lowercase());
+
+        Ok(languages
+            .into_iter()
+            .map(|language| {
+                let students: Vec<Student> = tables
+                    .students
+                    .iter()
+                    .filter(|s| s.language == language.code)
+                    .cloned()
+                    .collect();
+
+                (language.name, students)
+            })
+            .filter(|(_, students)| !students.is_empty())
+            .collect())
+    }
+
+    async fn list_students_group_by_os(&self) -> AppResult<Vec<(String, Vec<Student>)>> {
+        let tables = self.database.read();
+
+        let mut operating_systems = tables.operating_systems.clone();
+        operating_systems.sort_by_key(|os| os.name.to_lowercase());
+
+        Ok(operating_systems
+            .into_iter()
+            .map(|os| {
+                let students: Vec<Student> = tables
+                    .students
+                    .iter()
+                    .filter(|s| s.operational_systems.contains(&os.name))
+                    .cloned()
+                    .collect();
+
+                (os.name, students)
+            })
+            .filter(|(_, students)| !students.is_empty())
+            .collect())
+    }
+
+    async fn delete(&self, student_id: &str) -> AppResult<()> {
+        let mut guard = self.database.write();
+        let tables = &mut *guard;
+        let deleted_at = time::OffsetDateTime::now_utc();
+
+        memory::Tables::trash(
+            &mut tables.avatars,
+            &mut tables.trash,
+            deleted_at,
+            |a| a.student_id == student_id,
+            memory::TrashedRow::Avatar,
+        );
+        memory::Tables::trash(
+            &mut tables.students,
+            &mut tables.trash,
+            deleted_at,
+            |s| s.id == student_id,
+            memory::TrashedRow::Student,
+        );
+
+        Ok(())
+    }
+
+    async fn get_by_id(&self, student_id: &str) -> AppResult<Option<Student>> {
+        let tables = self.database.read();
+
+        Ok(tables.students.iter().find(|s| s.id == student_id).cloned())
+    }
+
+    async fn get_by_email(&self, email: &str) -> AppResult<Option<Student>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .students
+            .iter()
+            .find(|s| s.email.to_lowercase() == email.to_lowercase())
+            .cloned())
+    }
+
+    async fn list_student_that_doesnt_have_avatar(&self) -> AppResult<Vec<Student>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .students
+            .iter()
+            .filter(|s| !tables.avatars.iter().any(|a| a.student_id == s.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn list_student_with_avatar(
+        &self,
+        filter: &StudentFilter,
+        sort: &StudentSort,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Student, Option<Avatar>)>> {
+        let tables = self.database.read();
+
+        if let Some(cursor) = page.anchor() {
+            if !tables.students.iter().any(|s| s.id == *cursor) {
+                return Err(unknown_cursor("student"));
+            }
+        }
+
+        let course_name = |student: &Student| {
+            tables
+                .courses
+                .iter()
+                .find(|c| c.id == student.course_id)
+                .map(|c| c.name.clone())
+                .unwrap_or_default()
+        };
+
+        let matches = |student: &Student| {
+            let full_name = format!("{} {}", student.first_name, student.last_name);
+            let domain = student.email.split('@').nth(1).unwrap_or_default();
+
+            filter
+                .course_id
+                .as_ref()
+                .is_none_or(|c| *c == student.course_id)
+                && filter
+                    .language
+                    .as_ref()
+                    .is_none_or(|l| *l == student.language)
+                && filter
+                    .operational_system
+                    .as_ref()
+                    .is_none_or(|os| student.operational_systems.contains(os))
+                && filter
+                    .email_domain
+                    .as_ref()
+                    .is_none_or(|d| d.eq_ignore_ascii_case(domain))
+                && filter
+                    .name
+                    .as_ref()
+                    .is_none_or(|n| full_name.to_lowercase().contains(&n.to_lowercase()))
+        };
+
+        // The rows are kept in insertion order, which stands in for created_at.
+        let mut students: Vec<_> = tables
+            .students
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| matches(s))
+            .map(|(position, s)| {
+                let key = match sort.field {
+                    SortField::Name => vec![s.first_name.clone(), s.last_name.clone()],
+                    SortField::Email => vec![s.email.clone()],
+                    SortField::Course => {
+                        vec![course_name(s), s.first_name.clone(), s.last_name.clone()]
+                    }
+                    SortField::CreatedAt => vec![format!("{position:010}")],
+                };
+                let avatar = tables.avatars.iter().find(|a| a.student_id == s.id);
+
+                (key, s.clone(), avatar.cloned())
+            })
+            .collect();
+        students.sort_by(|(a, a_student, _), (b, b_student, _)| {
+            (a, &a_student.id).cmp(&(b, &b_student.id))
+        });
+        if sort.descending {
+            students.reverse();
+        }
+
+        let students = students
+            .into_iter()
+            .map(|(_, student, avatar)| (student, avatar))
+            .collect();
+
+        Ok(memory::paginate(students, page, |(student, _)| &student.id))
+    }
+
+    async fn get_student_with_course_and_subjects(
+        &self,
+        student_id: &str,
+    ) -> AppResult<(Student, Course, Vec<Subject>)> {
+        let tables = self.database.read();
+
+        let student = tables
+            .students
+            .iter()
+            .find(|s| s.id == student_id)
+            .ok_or_else(|| AppError::NotFound("Student does not exists".to_string()))?;
+
+        let course = tables
+            .courses
+            .iter()
+            .find(|c| c.id == student.course_id)
+            .ok_or_else(|| AppError::NotFound("Student does not exists".to_string()))?;
+
+        let subjects = tables
+            .subject_courses
+            .iter()
+            .filter(|(_, course_id)| *course_id == course.id)
+            .filter_map(|(subject_id, _)| tables.subjects.iter().find(|s| s.id == *subject_id))
+            .cloned()
+            .collect();
+
+        Ok((student.clone(), course.clone(), subjects))
     }
 }
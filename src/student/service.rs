@@ -1,35 +1,101 @@
-use std::error::Error;
+use std::sync::Arc;
+
+use tracing::instrument;
 
-use once_cell::sync::Lazy;
 use serde::Serialize;
 
 use crate::{
     avatar::model::Avatar,
-    course::{self, model::Course},
+    course::{model::Course, repository::Repository as CourseRepository},
+    error::{AppError, AppResult},
+    language::repository::Repository as LanguageRepository,
+    operating_system::repository::Repository as OperatingSystemRepository,
+    pagination::{Page, PageRequest},
     subject::model::Subject,
+    validation::FieldErrors,
 };
 
-use super::{model::Student, repository::Repository};
+use super::{
+    model::{Student, StudentFilter, StudentSort},
+    repository::Repository,
+};
 
 #[derive(Debug, Serialize)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum GroupBy {
     COURSE,
     LANGUAGE,
     OS,
 }
 
-pub static SERVICE: Lazy<Service> = Lazy::new(Service::new);
 pub struct Service {
-    repository: Repository,
+    repository: Arc<dyn Repository>,
+    course_repository: Arc<dyn CourseRepository>,
+    operating_system_repository: Arc<dyn OperatingSystemRepository>,
+    language_repository: Arc<dyn LanguageRepository>,
 }
 
 impl Service {
-    pub fn new() -> Self {
+    pub fn new(
+        repository: Arc<dyn Repository>,
+        course_repository: Arc<dyn CourseRepository>,
+        operating_system_repository: Arc<dyn OperatingSystemRepository>,
+        language_repository: Arc<dyn LanguageRepository>,
+    ) -> Self {
         Service {
-            repository: Repository::new(),
+            repository,
+            course_repository,
+            operating_system_repository,
+            language_repository,
+        }
+    }
+
+    /// Checks the course, the language and the operating systems against their tables and
+    /// returns them as they are stored: the code of the language and each operating system once,
+    /// in the order they are read back.
+    async fn resolve_references(
+        &self,
+        course: &str,
+        language: &str,
+        names: &[String],
+    ) -> AppResult<(String, Vec<String>)> {
+        let course_exists = self.course_repository.get_by_id(course).await?.is_some();
+
+        let languages = self.language_repository.list_all().await?;
+        let mut codes: Vec<&str> = languages.iter().map(|l| l.code.as_str()).collect();
+        codes.sort_unstable();
+
+        let operating_systems = self.operating_system_repository.list_all().await?;
+        let options: Vec<&str> = operating_systems
+            .iter()
+            .map(|operating_system| operating_system.name.as_str())
+            .collect();
+
+        // Tags are compared whatever their case and separator, so "pt_br" is stored as pt-BR.
+        let tag = language.trim().replace('_', "-");
+        let code = codes
+            .iter()
+            .find(|code| code.eq_ignore_ascii_case(&tag))
+            .map_or(tag, |code| code.to_string());
+        let mut errors = FieldErrors::new();
+        if !course_exists {
+            errors.add("course", "is not an existing course");
         }
+        errors
+            .one_of("language", &code, &codes)
+            .all_of("operational_systems", names, &options);
+        errors.into_result()?;
+
+        let operational_systems = operating_systems
+            .into_iter()
+            .map(|operating_system| operating_system.name)
+            .filter(|name| names.contains(name))
+            .collect();
+
+        Ok((code, operational_systems))
     }
 
+    #[instrument(level = "debug", skip_all)]
     pub async fn save(
         &self,
         first_name: &str,
@@ -38,47 +104,73 @@ impl Service {
         language: &str,
         email: &str,
         operational_systems: Vec<&String>,
-    ) -> Result<Student, Box<dyn Error>> {
-        let course = match course::service::SERVICE.get_course_by_id(&course).await? {
-            None => return Err("Course does not exists!".into()),
-            Some(course) => course,
-        };
-
+    ) -> AppResult<Student> {
+        let names: Vec<String> = operational_systems.into_iter().cloned().collect();
+        let (language, operational_systems) =
+            self.resolve_references(course, language, &names).await?;
         let student = Student::new(
             first_name,
             last_name,
-            &course.get_id(),
-            language,
+            course,
+            &language,
             email,
-            operational_systems,
+            operational_systems.iter().collect(),
         );
 
-        let student = self.repository.save(student).await?;
+        let student = self.repository.insert(student).await?;
 
         Ok(student)
     }
 
-    pub async fn delete(&self, student_id: String) -> Result<(), Box<dyn Error>> {
-        self.repository.delete(student_id).await
+    #[instrument(level = "debug", skip_all)]
+    pub async fn update(&self, mut student: Student) -> AppResult<Student> {
+        (student.language, student.operational_systems) = self
+            .resolve_references(
+                student.get_course(),
+                &student.language,
+                &student.operational_systems,
+            )
+            .await?;
+
+        self.repository.update(student).await
     }
 
-    pub async fn get_student_by_id(
-        &self,
-        student_id: &str,
-    ) -> Result<Option<Student>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_id(&self, student_id: &str) -> AppResult<Option<Student>> {
         self.repository.get_by_id(student_id).await
     }
 
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_email(&self, email: &str) -> AppResult<Option<Student>> {
+        self.repository.get_by_email(email).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete(&self, student_id: String) -> AppResult<()> {
+        if self.repository.get_by_id(&student_id).await?.is_none() {
+            return Err(AppError::NotFound("Student does not exists".to_string()));
+        }
+
+        self.repository.delete(&student_id).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
     pub async fn list_students_with_avatar(
         &self,
-    ) -> Result<Vec<(Student, Option<Avatar>)>, Box<dyn Error>> {
-        self.repository.list_student_with_avatar().await
+        filter: &StudentFilter,
+        sort: &StudentSort,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Student, Option<Avatar>)>> {
+        self.repository
+            .list_student_with_avatar(filter, sort, page)
+            .await
     }
 
+    #[instrument(level = "debug", skip_all)]
     pub async fn list_group_by(
         &self,
         group_by: &GroupBy,
-    ) -> Result<Vec<(String, Vec<Student>)>, Box<dyn Error>> {
+    ) -> AppResult<Vec<(String, Vec<Student>)>> {
         match group_by {
             GroupBy::COURSE => self.repository.list_students_group_by_course().await,
             GroupBy::LANGUAGE => self.repository.list_students_group_by_language().await,
@@ -86,23 +178,16 @@ impl Service {
         }
     }
 
-    pub async fn list_students_by_course_id(
-        &self,
-        course_id: &String,
-    ) -> Result<Vec<Student>, Box<dyn Error>> {
-        self.repository.list_students_by_course_id(course_id).await
-    }
-
-    pub async fn list_students_that_doesnt_have_avatar(
-        &self,
-    ) -> Result<Vec<Student>, Box<dyn Error>> {
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list_students_that_doesnt_have_avatar(&self) -> AppResult<Vec<Student>> {
         self.repository.list_student_that_doesnt_have_avatar().await
     }
 
+    #[instrument(level = "debug", skip(self))]
     pub async fn get_student_with_course_and_subjects(
         &self,
         student_id: &str,
-    ) -> Result<(Student, Course, Vec<Subject>), Box<dyn Error>> {
+    ) -> AppResult<(Student, Course, Vec<Subject>)> {
         self.repository
             .get_student_with_course_and_subjects(student_id)
             .await
@@ -1,67 +1,382 @@
 use axum::{
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
-use axum_extra::extract::Form;
+use axum_extra::extract::{Form, WithRejection};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    course::{self, model::Course},
-    custom::HtmlResponse,
-    subject,
-    view::render_template,
+    course::model::Course,
+    custom::{HtmlResponse, JsonError},
+    error::{AppError, AppResult},
+    pagination::{Page, PageQuery},
+    state::AppState,
+    validation::{FieldErrors, TakenBy, Validate},
 };
 
 use super::model::Subject;
 
-#[derive(Deserialize, Debug)]
+const CODE_MAX_LENGTH: usize = 20;
+
+const NAME_MAX_LENGTH: usize = 100;
+
+const PROGRAM_MAX_LENGTH: usize = 5000;
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct CreateSubjectControllerModel {
+    #[serde(default)]
     code: String,
+    #[serde(default)]
     name: String,
+    #[serde(default)]
     program: String,
+    #[serde(default)]
     courses: Vec<String>,
 }
 
+impl Validate for CreateSubjectControllerModel {
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+        errors
+            .required("code", &self.code)
+            .max_length("code", &self.code, CODE_MAX_LENGTH)
+            .required("name", &self.name)
+            .max_length("name", &self.name, NAME_MAX_LENGTH)
+            .required("program", &self.program)
+            .max_length("program", &self.program, PROGRAM_MAX_LENGTH);
+        errors.into_result()
+    }
+}
+
+/// `?course` preselects a course of the form, used by the links of the course page.
+#[derive(Deserialize, Debug, Default)]
+pub struct CreateSubjectQueryParam {
+    course: Option<String>,
+}
+
+/// `subject` holds the submitted values when the form is rendered again to fix its `errors`.
 #[derive(Serialize)]
-pub struct ListSubjectHtmlControllerModel {
+pub struct CreateSubjectHtmlControllerModel {
+    courses: Vec<Course>,
+    selected_courses: Vec<String>,
+    subject: Option<CreateSubjectControllerModel>,
+    errors: FieldErrors,
+    code_taken_by: Option<TakenBy>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DeleteSubjectQueryParam {
+    #[serde(default)]
+    cascade: bool,
+}
+
+#[derive(Serialize)]
+pub struct SubjectWithCoursesControllerModel {
     subject: Subject,
     courses: Vec<Course>,
 }
 
-pub async fn list_html() -> impl IntoResponse {
-    match super::service::SERVICE.list_with_courses().await {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(subjects) => {
-            let context: Vec<_> = subjects
-                .into_iter()
-                .map(|subject| ListSubjectHtmlControllerModel {
-                    subject: subject.0,
-                    courses: subject.1,
-                })
-                .collect();
-            render_template("subject/list", context.into()).to_html_response()
+pub async fn list_html(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+) -> AppResult<Response> {
+    let context = state
+        .subjects
+        .list_with_courses(&page.try_into()?)
+        .await?
+        .map(|subject| SubjectWithCoursesControllerModel {
+            subject: subject.0,
+            courses: subject.1,
+        });
+
+    Ok(state
+        .views
+        .render_template("subject/list", context.into())?
+        .to_html_response())
+}
+
+#[derive(Serialize)]
+pub struct EditSubjectHtmlControllerModel {
+    subject: Subject,
+    linked: Vec<String>,
+    courses: Vec<Course>,
+    errors: FieldErrors,
+    code_taken_by: Option<TakenBy>,
+}
+
+/// Subject other than `subject_id` that already uses the code with the same program.
+async fn code_taken_by(
+    state: &AppState,
+    payload: &CreateSubjectControllerModel,
+    subject_id: Option<&str>,
+) -> AppResult<Option<TakenBy>> {
+    Ok(state
+        .subjects
+        .get_by_code(&payload.code, &payload.program)
+        .await?
+        .filter(|owner| Some(owner.id.as_str()) != subject_id)
+        .map(|owner| TakenBy {
+            entity: "subject",
+            label: format!("{} - {}", owner.code, owner.name),
+            id: owner.id,
+        }))
+}
+
+async fn find_subject(state: &AppState, subject_id: &str) -> AppResult<(Subject, Vec<Course>)> {
+    state
+        .subjects
+        .get_with_courses(subject_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Subject does not exists".to_string()))
+}
+
+pub async fn show_html(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+) -> AppResult<Response> {
+    let (subject, courses) = find_subject(&state, &subject_id).await?;
+    let context = SubjectWithCoursesControllerModel { subject, courses };
+
+    Ok(state
+        .views
+        .render_template("subject/show", context.into())?
+        .to_html_response())
+}
+
+pub async fn edit_html(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+) -> AppResult<Response> {
+    let (subject, linked) = find_subject(&state, &subject_id).await?;
+
+    let context = EditSubjectHtmlControllerModel {
+        subject,
+        linked: linked.into_iter().map(|course| course.id).collect(),
+        courses: state.courses.list_all_courses().await?,
+        errors: FieldErrors::new(),
+        code_taken_by: None,
+    };
+
+    Ok(state
+        .views
+        .render_template("subject/edit", context.into())?
+        .to_html_response())
+}
+
+pub async fn create_html(
+    State(state): State<AppState>,
+    Query(query): Query<CreateSubjectQueryParam>,
+) -> AppResult<Response> {
+    let context = CreateSubjectHtmlControllerModel {
+        courses: state.courses.list_all_courses().await?,
+        selected_courses: query.course.into_iter().collect(),
+        subject: None,
+        errors: FieldErrors::new(),
+        code_taken_by: None,
+    };
+
+    Ok(state
+        .views
+        .render_template("subject/create", context.into())?
+        .to_html_response())
+}
+
+async fn render_create_form(
+    state: &AppState,
+    payload: CreateSubjectControllerModel,
+    errors: FieldErrors,
+    code_taken_by: Option<TakenBy>,
+) -> AppResult<String> {
+    let context = CreateSubjectHtmlControllerModel {
+        courses: state.courses.list_all_courses().await?,
+        selected_courses: payload.courses.clone(),
+        subject: Some(payload),
+        errors,
+        code_taken_by,
+    };
+
+    state
+        .views
+        .render_template("subject/create", context.into())
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Form(payload): Form<CreateSubjectControllerModel>,
+) -> AppResult<Response> {
+    let invalid = payload.validate().err();
+    let taken_by = code_taken_by(&state, &payload, None).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        let html =
+            render_create_form(&state, payload, invalid.unwrap_or_default(), taken_by).await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    // The courses are checked by the service, one may have been trashed since the form loaded.
+    let saved = state
+        .subjects
+        .save(
+            &payload.code,
+            &payload.name,
+            &payload.program,
+            payload.courses.iter().map(|s| s.as_str()).collect(),
+        )
+        .await;
+
+    match saved {
+        Err(AppError::InvalidFields(errors)) => {
+            let html = render_create_form(&state, payload, errors, None).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
         }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to("/subjects").into_response()),
     }
 }
 
-pub async fn create_html() -> impl IntoResponse {
-    match course::service::SERVICE.list_courses().await {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(courses) => render_template("subject/create", courses.into()).to_html_response(),
+async fn render_edit_form(
+    state: &AppState,
+    subject_id: &str,
+    payload: CreateSubjectControllerModel,
+    errors: FieldErrors,
+    code_taken_by: Option<TakenBy>,
+) -> AppResult<String> {
+    let context = EditSubjectHtmlControllerModel {
+        subject: Subject::new_with_id(subject_id, &payload.code, &payload.name, &payload.program),
+        linked: payload.courses,
+        courses: state.courses.list_all_courses().await?,
+        errors,
+        code_taken_by,
+    };
+
+    state.views.render_template("subject/edit", context.into())
+}
+
+pub async fn edit(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+    Form(payload): Form<CreateSubjectControllerModel>,
+) -> AppResult<Response> {
+    let invalid = payload.validate().err();
+    let taken_by = code_taken_by(&state, &payload, Some(&subject_id)).await?;
+    if invalid.is_some() || taken_by.is_some() {
+        find_subject(&state, &subject_id).await?;
+
+        let html = render_edit_form(
+            &state,
+            &subject_id,
+            payload,
+            invalid.unwrap_or_default(),
+            taken_by,
+        )
+        .await?;
+
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response());
+    }
+
+    let updated = state
+        .subjects
+        .update(
+            &subject_id,
+            &payload.code,
+            &payload.name,
+            &payload.program,
+            payload.courses.iter().map(|s| s.as_str()).collect(),
+        )
+        .await;
+
+    match updated {
+        Err(AppError::InvalidFields(errors)) => {
+            find_subject(&state, &subject_id).await?;
+
+            let html = render_edit_form(&state, &subject_id, payload, errors, None).await?;
+
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, html.to_html_response()).into_response())
+        }
+        Err(error) => Err(error),
+        Ok(_) => Ok(Redirect::to(&format!("/subject/{subject_id}")).into_response()),
     }
 }
 
-pub async fn create(Form(payload): Form<CreateSubjectControllerModel>) -> impl IntoResponse {
-    match subject::service::SERVICE
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+    Query(params): Query<DeleteSubjectQueryParam>,
+) -> AppResult<StatusCode> {
+    state.subjects.delete(&subject_id, params.cascade).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_json(
+    State(state): State<AppState>,
+    WithRejection(Query(page), _): WithRejection<Query<PageQuery>, JsonError>,
+) -> Result<Json<Page<SubjectWithCoursesControllerModel>>, JsonError> {
+    let subjects = state
+        .subjects
+        .list_with_courses(&page.try_into()?)
+        .await?
+        .map(|(subject, courses)| SubjectWithCoursesControllerModel { subject, courses });
+
+    Ok(Json(subjects))
+}
+
+pub async fn show_json(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+) -> Result<Json<SubjectWithCoursesControllerModel>, JsonError> {
+    let (subject, courses) = find_subject(&state, &subject_id).await?;
+
+    Ok(Json(SubjectWithCoursesControllerModel { subject, courses }))
+}
+
+pub async fn create_json(
+    State(state): State<AppState>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateSubjectControllerModel>, JsonError>,
+) -> Result<impl IntoResponse, JsonError> {
+    payload.validate()?;
+    let subject = state
+        .subjects
         .save(
             &payload.code,
             &payload.name,
             &payload.program,
             payload.courses.iter().map(|s| s.as_str()).collect(),
         )
-        .await
-    {
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        Ok(_) => Redirect::to("/subjects").into_response(),
-    }
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(subject)))
+}
+
+pub async fn update_json(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateSubjectControllerModel>, JsonError>,
+) -> Result<Json<Subject>, JsonError> {
+    payload.validate()?;
+    let subject = state
+        .subjects
+        .update(
+            &subject_id,
+            &payload.code,
+            &payload.name,
+            &payload.program,
+            payload.courses.iter().map(|s| s.as_str()).collect(),
+        )
+        .await?;
+
+    Ok(Json(subject))
+}
+
+pub async fn delete_json(
+    State(state): State<AppState>,
+    Path(subject_id): Path<String>,
+    WithRejection(Query(params), _): WithRejection<Query<DeleteSubjectQueryParam>, JsonError>,
+) -> Result<StatusCode, JsonError> {
+    state.subjects.delete(&subject_id, params.cascade).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -19,6 +19,15 @@ impl Subject {
         }
     }
 
+    pub fn new_with_id(id: &str, code: &str, name: &str, program: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            code: code.to_string(),
+            name: name.to_string(),
+            program: program.to_string(),
+        }
+    }
+
     fn generate_id() -> String {
         static ALPHABET: [char; 35] = [
             '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
@@ -1,21 +1,54 @@
-use std::error::Error;
-
 use super::model::Subject;
-use crate::{course::model::Course, infra};
+use crate::{
+    course::model::Course,
+    error::{AppError, AppResult},
+    pagination::{unknown_cursor, Page, PageRequest},
+};
+#[cfg(test)]
+use crate::{infra::memory, trash::model::TrashKind};
+use async_trait::async_trait;
 use nanoid::nanoid;
 use serde_json;
 use sqlx::Row;
 use sqlx::{Pool, Postgres};
+use tracing::instrument;
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// Fails with `BadRequest` when the cursor is not a live subject.
+    async fn list_with_courses(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Subject, Vec<Course>)>>;
+
+    async fn get_with_courses(&self, subject_id: &str)
+        -> AppResult<Option<(Subject, Vec<Course>)>>;
+
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Subject>>;
+
+    /// The live subject with this code, whatever its case, and this program.
+    async fn get_by_code(&self, code: &str, program: &str) -> AppResult<Option<Subject>>;
 
-pub struct Repository {
-    database: &'static Pool<Postgres>,
+    /// Keeps the links to courses that are still listed, so only added and removed courses
+    /// change, and fails with `NotFound` when the subject does not exist.
+    async fn update(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject>;
+
+    /// Moves the subject to the trash. Its course links are kept for a restore and only removed
+    /// when it is purged, unless `unlink_courses` removes them in the same transaction. Callers
+    /// decide whether a linked subject may be deleted.
+    async fn delete(&self, subject_id: &str, unlink_courses: bool) -> AppResult<()>;
+
+    /// Inserts a new subject, an id that is already used fails, trashed subjects included.
+    async fn save(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject>;
 }
 
-impl Repository {
-    pub fn new() -> Self {
-        Repository {
-            database: infra::db::DB_POOL.get().expect("Unable to get DB_POOL"),
-        }
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
     }
 
     fn generate_relation_id() -> String {
@@ -27,24 +60,93 @@ impl Repository {
 
         nanoid!(10, &ALPHABET)
     }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "subject.list_with_courses"))]
+    async fn list_with_courses(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Subject, Vec<Course>)>> {
+        if let Some(cursor) = page.anchor() {
+            let known = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM subject WHERE id = $1 AND deleted_at IS NULL) "known!"
+                "#,
+                cursor
+            )
+            .fetch_one(&self.database)
+            .await?;
+
+            if !known {
+                return Err(unknown_cursor("subject"));
+            }
+        }
+
+        // The previous page ends right before the row of `before`.
+        let page = &match &page.before {
+            Some(before) => {
+                let position = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) "position!" FROM subject WHERE deleted_at IS NULL
+                        AND (code, id) < (SELECT code, id FROM subject WHERE id = $1)
+                    "#,
+                    before
+                )
+                .fetch_one(&self.database)
+                .await?;
+
+                page.ending_before(position)
+            }
+            None => page.clone(),
+        };
 
-    pub async fn list_with_courses(&self) -> Result<Vec<(Subject, Vec<Course>)>, Box<dyn Error>> {
         let rows = sqlx::query(
             r#"
+            with listed as (
+                select
+                    s.id, s.code, s.name, s.program
+                from
+                    subject s
+                where
+                    s.deleted_at is null
+                    and (
+                        $1::text is null
+                        or (s.code, s.id) > (select code, id from subject where id = $1)
+                    )
+                order by
+                    s.code, s.id
+                limit $2 offset $3
+            )
             select
                 s.*,
-                JSON_AGG(c.*) "courses"
+                COALESCE(JSON_AGG(c.*) FILTER (WHERE c.id IS NOT NULL), '[]') "courses"
             from
-                subject s
-            inner join subject_course on
+                listed s
+            left join subject_course on
                 s.id = subject_course.subject_id
-            inner join course c on
+            left join course c on
                 subject_course.course_id = c.id
+                and c.deleted_at is null
             group by
-                s.id
+                s.id, s.code, s.name, s.program
+            order by
+                s.code, s.id
             "#,
         )
-        .fetch_all(self.database)
+        .bind(&page.cursor)
+        .bind(page.limit())
+        .bind(page.offset())
+        .fetch_all(&self.database)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) "total!" FROM subject WHERE deleted_at IS NULL
+            "#
+        )
+        .fetch_one(&self.database)
         .await?;
 
         let subject_with_courses: Vec<(Subject, Vec<Course>)> = rows
@@ -64,24 +166,187 @@ impl Repository {
             })
             .collect();
 
-        Ok(subject_with_courses)
+        Ok(Page::new(
+            subject_with_courses,
+            total,
+            page,
+            |(subject, _)| &subject.id,
+        ))
     }
 
-    pub async fn save(
+    #[instrument(level = "debug", skip_all, fields(query = "subject.get_with_courses"))]
+    async fn get_with_courses(
         &self,
-        subject: &Subject,
-        courses_id: Vec<&str>,
-    ) -> Result<Subject, Box<dyn Error>> {
-        let mut tx = match self.database.begin().await {
-            Err(_) => return Err("Error when try to open a new transaction".into()),
-            Ok(tx) => tx,
-        };
+        subject_id: &str,
+    ) -> AppResult<Option<(Subject, Vec<Course>)>> {
+        let row = sqlx::query(
+            r#"
+            select
+                s.*,
+                COALESCE(JSON_AGG(c.*) FILTER (WHERE c.id IS NOT NULL), '[]') "courses"
+            from
+                subject s
+            left join subject_course on
+                s.id = subject_course.subject_id
+            left join course c on
+                subject_course.course_id = c.id
+                and c.deleted_at is null
+            where
+                s.id = $1
+                and s.deleted_at is null
+            group by
+                s.id
+            "#,
+        )
+        .bind(subject_id)
+        .fetch_optional(&self.database)
+        .await?;
+
+        let subject_with_courses = row.map(|row| {
+            let subject = Subject {
+                id: row.get("id"),
+                code: row.get("code"),
+                name: row.get("name"),
+                program: row.get("program"),
+            };
+
+            let courses: Vec<Course> = serde_json::from_value(row.get("courses")).unwrap_or(vec![]);
+
+            (subject, courses)
+        });
+
+        Ok(subject_with_courses)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "subject.list_by_course_id"))]
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Subject>> {
+        let subjects = sqlx::query_as!(
+            Subject,
+            r#"
+            SELECT s.id, s.code, s.name, s.program
+            FROM subject s
+            INNER JOIN subject_course sc ON sc.subject_id = s.id
+            WHERE sc.course_id = $1 AND s.deleted_at IS NULL
+            ORDER BY s.code, s.id
+            "#,
+            course_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        Ok(subjects)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "subject.get_by_code"))]
+    async fn get_by_code(&self, code: &str, program: &str) -> AppResult<Option<Subject>> {
+        let subject = sqlx::query_as!(
+            Subject,
+            r#"
+            SELECT id, code, name, program
+            FROM subject
+            WHERE lower(code) = lower($1) AND md5(program) = md5($2) AND deleted_at IS NULL
+            "#,
+            code,
+            program
+        )
+        .fetch_optional(&self.database)
+        .await?;
+
+        Ok(subject)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "subject.update"))]
+    async fn update(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject> {
+        let mut tx = self.database.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE subject SET code = $2, name = $3, program = $4
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            subject.get_id(),
+            subject.get_code(),
+            subject.get_name(),
+            subject.get_program()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Subject does not exists".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            DELETE FROM subject_course
+            WHERE subject_id = $1 AND NOT (course_id = ANY($2))
+                AND course_id IN (SELECT id FROM course WHERE deleted_at IS NULL)
+            "#,
+        )
+        .bind(subject.get_id())
+        .bind(&courses_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut relations_ids = vec![];
+        relations_ids.resize_with(courses_id.len(), PostgresRepository::generate_relation_id);
+
+        sqlx::query(
+            r#"
+            INSERT INTO subject_course (id, subject_id, course_id)
+            SELECT * FROM UNNEST($1, $2, $3)
+            ON CONFLICT (subject_id, course_id) DO NOTHING
+            "#,
+        )
+        .bind(relations_ids)
+        .bind(vec![subject.get_id().clone(); courses_id.len()])
+        .bind(courses_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(subject.clone())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "subject.delete"))]
+    async fn delete(&self, subject_id: &str, unlink_courses: bool) -> AppResult<()> {
+        let mut tx = self.database.begin().await?;
+
+        let deleted = sqlx::query!(
+            r#"
+            UPDATE subject SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            subject_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if unlink_courses && deleted.rows_affected() > 0 {
+            sqlx::query!(
+                r#"
+                DELETE FROM subject_course WHERE subject_id = $1
+                "#,
+                subject_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "subject.save"))]
+    async fn save(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject> {
+        let mut tx = self.database.begin().await?;
 
         sqlx::query!(
             r#"
             INSERT INTO subject (id, code, name, program)
             VALUES ($1, $2, $3, $4)
-            ON CONFLICT (id) DO UPDATE SET code=excluded.code, name=excluded.name, program=excluded.program
             "#,
             subject.get_id(),
             subject.get_code(),
@@ -92,7 +357,7 @@ impl Repository {
         .await?;
 
         let mut relations_ids = vec![];
-        relations_ids.resize_with(courses_id.len(), Repository::generate_relation_id);
+        relations_ids.resize_with(courses_id.len(), PostgresRepository::generate_relation_id);
 
         sqlx::query(
             r#"
@@ -112,3 +377,196 @@ impl Repository {
         Ok(subject.clone())
     }
 }
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn courses_of(tables: &memory::Tables, subject_id: &str) -> Vec<Course> {
+        tables
+            .subject_courses
+            .iter()
+            .filter(|(s, _)| s == subject_id)
+            .filter_map(|(_, course_id)| tables.courses.iter().find(|c| c.id == *course_id))
+            .cloned()
+            .collect()
+    }
+
+    fn check_code(tables: &memory::Tables, subject: &Subject) -> AppResult<()> {
+        if tables.subjects.iter().any(|s| {
+            s.id != subject.id
+                && s.code.to_lowercase() == subject.code.to_lowercase()
+                && s.program == subject.program
+        }) {
+            return Err(memory::unique_violation("subject_code_key"));
+        }
+
+        Ok(())
+    }
+
+    fn link_courses(
+        courses: &[Course],
+        links: &mut Vec<(String, String)>,
+        subject_id: &str,
+        courses_id: Vec<&str>,
+    ) -> AppResult<()> {
+        for course_id in courses_id {
+            if !courses.iter().any(|c| c.id == course_id) {
+                return Err(memory::constraint_violation(
+                    "subject_course_course_id_fkey",
+                ));
+            }
+
+            if links.iter().any(|(s, c)| s == subject_id && c == course_id) {
+                return Err(memory::constraint_violation(
+                    "subject_course_subject_id_course_id_key",
+                ));
+            }
+
+            links.push((subject_id.to_string(), course_id.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn list_with_courses(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Subject, Vec<Course>)>> {
+        let tables = self.database.read();
+
+        if let Some(cursor) = page.anchor() {
+            if !tables.subjects.iter().any(|s| s.id == *cursor) {
+                return Err(unknown_cursor("subject"));
+            }
+        }
+
+        let mut subjects: Vec<_> = tables
+            .subjects
+            .iter()
+            .map(|subject| (subject.clone(), Self::courses_of(&tables, &subject.id)))
+            .collect();
+        subjects.sort_by(|(a, _), (b, _)| (&a.code, &a.id).cmp(&(&b.code, &b.id)));
+
+        Ok(memory::paginate(subjects, page, |(subject, _)| &subject.id))
+    }
+
+    async fn get_with_courses(
+        &self,
+        subject_id: &str,
+    ) -> AppResult<Option<(Subject, Vec<Course>)>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .subjects
+            .iter()
+            .find(|s| s.id == subject_id)
+            .map(|subject| (subject.clone(), Self::courses_of(&tables, subject_id))))
+    }
+
+    async fn list_by_course_id(&self, course_id: &str) -> AppResult<Vec<Subject>> {
+        let tables = self.database.read();
+
+        let mut subjects: Vec<Subject> = tables
+            .subject_courses
+            .iter()
+            .filter(|(_, c)| c == course_id)
+            .filter_map(|(subject_id, _)| tables.subjects.iter().find(|s| s.id == *subject_id))
+            .cloned()
+            .collect();
+        subjects.sort_by(|a, b| (&a.code, &a.id).cmp(&(&b.code, &b.id)));
+
+        Ok(subjects)
+    }
+
+    async fn get_by_code(&self, code: &str, program: &str) -> AppResult<Option<Subject>> {
+        let tables = self.database.read();
+
+        Ok(tables
+            .subjects
+            .iter()
+            .find(|s| s.code.to_lowercase() == code.to_lowercase() && s.program == program)
+            .cloned())
+    }
+
+    async fn update(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject> {
+        let mut tables = self.database.write();
+
+        if !tables.subjects.iter().any(|s| s.id == subject.id) {
+            return Err(AppError::NotFound("Subject does not exists".to_string()));
+        }
+        Self::check_code(&tables, subject)?;
+
+        let mut links = tables.subject_courses.clone();
+        // Links to trashed courses are not part of the form, they are kept for a restore.
+        links.retain(|(s, c)| {
+            *s != subject.id
+                || courses_id.contains(&c.as_str())
+                || !tables.courses.iter().any(|course| course.id == *c)
+        });
+
+        let added: Vec<_> = courses_id
+            .into_iter()
+            .filter(|course_id| {
+                !links
+                    .iter()
+                    .any(|(s, c)| *s == subject.id && c == course_id)
+            })
+            .collect();
+        Self::link_courses(&tables.courses, &mut links, &subject.id, added)?;
+
+        if let Some(existing) = tables.subjects.iter_mut().find(|s| s.id == subject.id) {
+            *existing = subject.clone();
+        }
+        tables.subject_courses = links;
+
+        Ok(subject.clone())
+    }
+
+    async fn delete(&self, subject_id: &str, unlink_courses: bool) -> AppResult<()> {
+        let mut guard = self.database.write();
+        let tables = &mut *guard;
+
+        if unlink_courses && tables.subjects.iter().any(|s| s.id == subject_id) {
+            tables.subject_courses.retain(|(s, _)| s != subject_id);
+        }
+        memory::Tables::trash(
+            &mut tables.subjects,
+            &mut tables.trash,
+            time::OffsetDateTime::now_utc(),
+            |s| s.id == subject_id,
+            memory::TrashedRow::Subject,
+        );
+
+        Ok(())
+    }
+
+    async fn save(&self, subject: &Subject, courses_id: Vec<&str>) -> AppResult<Subject> {
+        let mut tables = self.database.write();
+        if tables.subjects.iter().any(|s| s.id == subject.id)
+            || tables.trashed(TrashKind::Subject, &subject.id).is_some()
+        {
+            return Err(memory::constraint_violation("subject_pkey"));
+        }
+        Self::check_code(&tables, subject)?;
+        let mut links = tables.subject_courses.clone();
+
+        Self::link_courses(&tables.courses, &mut links, &subject.id, courses_id)?;
+
+        tables.subjects.push(subject.clone());
+        tables.subject_courses = links;
+
+        Ok(subject.clone())
+    }
+}
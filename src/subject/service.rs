@@ -1,33 +1,116 @@
-use super::{model::Subject, repository::Repository};
-use crate::course::model::Course;
-use once_cell::sync::Lazy;
-use std::error::Error;
+use std::sync::Arc;
+
+use tracing::instrument;
 
-pub static SERVICE: Lazy<Service> = Lazy::new(Service::new);
+use super::{model::Subject, repository::Repository};
+use crate::{
+    course::{model::Course, repository::Repository as CourseRepository},
+    error::{AppError, AppResult},
+    pagination::{Page, PageRequest},
+    validation::FieldErrors,
+};
 
 pub struct Service {
-    repository: Repository,
+    repository: Arc<dyn Repository>,
+    course_repository: Arc<dyn CourseRepository>,
 }
 
 impl Service {
-    pub fn new() -> Self {
+    pub fn new(
+        repository: Arc<dyn Repository>,
+        course_repository: Arc<dyn CourseRepository>,
+    ) -> Self {
         Service {
-            repository: Repository::new(),
+            repository,
+            course_repository,
+        }
+    }
+
+    /// Checks that every course exists and drops the repeated ones, a course is linked once.
+    #[instrument(level = "debug", skip_all)]
+    async fn linkable_courses<'a>(&self, mut courses_id: Vec<&'a str>) -> AppResult<Vec<&'a str>> {
+        courses_id.sort_unstable();
+        courses_id.dedup();
+
+        let mut errors = FieldErrors::new();
+        for course_id in &courses_id {
+            if self.course_repository.get_by_id(course_id).await?.is_none() {
+                errors.add("courses", format!("{course_id} is not an existing course"));
+            }
         }
+        errors.into_result()?;
+
+        Ok(courses_id)
     }
 
+    #[instrument(level = "debug", skip_all)]
     pub async fn save(
         &self,
         code: &str,
         name: &str,
         program: &str,
         courses_id: Vec<&str>,
-    ) -> Result<Subject, Box<dyn Error>> {
+    ) -> AppResult<Subject> {
+        let courses_id = self.linkable_courses(courses_id).await?;
+
         let subject = Subject::new(code, name, program);
         self.repository.save(&subject, courses_id).await
     }
 
-    pub async fn list_with_courses(&self) -> Result<Vec<(Subject, Vec<Course>)>, Box<dyn Error>> {
-        self.repository.list_with_courses().await
+    #[instrument(level = "debug", skip_all)]
+    pub async fn update(
+        &self,
+        subject_id: &str,
+        code: &str,
+        name: &str,
+        program: &str,
+        courses_id: Vec<&str>,
+    ) -> AppResult<Subject> {
+        let courses_id = self.linkable_courses(courses_id).await?;
+
+        let subject = Subject::new_with_id(subject_id, code, name, program);
+        self.repository.update(&subject, courses_id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_by_code(&self, code: &str, program: &str) -> AppResult<Option<Subject>> {
+        self.repository.get_by_code(code, program).await
+    }
+
+    /// A subject that is still linked to courses is only deleted when `cascade` asks to remove
+    /// the links as well, it is then restored from the trash without them.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete(&self, subject_id: &str, cascade: bool) -> AppResult<()> {
+        let (_, courses) = self
+            .repository
+            .get_with_courses(subject_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Subject does not exists".to_string()))?;
+
+        if !courses.is_empty() && !cascade {
+            return Err(AppError::Conflict(format!(
+                "Unable to delete the subject because it is linked to {} course(s), \
+                 delete it with its links to remove them too",
+                courses.len()
+            )));
+        }
+
+        self.repository.delete(subject_id, cascade).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_with_courses(
+        &self,
+        subject_id: &str,
+    ) -> AppResult<Option<(Subject, Vec<Course>)>> {
+        self.repository.get_with_courses(subject_id).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list_with_courses(
+        &self,
+        page: &PageRequest,
+    ) -> AppResult<Page<(Subject, Vec<Course>)>> {
+        self.repository.list_with_courses(page).await
     }
 }
@@ -0,0 +1,192 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+
+#[tokio::test]
+async fn a_student_can_only_have_one_avatar() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    app.create_avatar("Nebula", &student_id).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/avatars",
+            Some(json!({ "name": "Quasar", "student": student_id })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn avatar_form_only_offers_students_without_avatar() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let with_avatar = app.create_student("Ana", &course_id).await;
+    app.create_student("Bruno", &course_id).await;
+    app.create_avatar("Nebula", &with_avatar).await;
+
+    let response = app.get("/avatar/create").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Bruno"));
+    assert!(!response.body.contains("Ana"));
+
+    let response = app.get("/avatars").await;
+    assert!(response.body.contains("Nebula"));
+}
+
+#[tokio::test]
+async fn renames_and_deletes_an_avatar() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    let avatar_id = app.create_avatar("Nebula", &student_id).await;
+    let uri = format!("/api/v1/avatars/{avatar_id}");
+
+    let response = app
+        .request(Method::PUT, &uri, Some(json!({ "name": "Quasar" })))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["fantasy_name"], "Quasar");
+
+    assert_eq!(
+        app.request(Method::DELETE, &uri, None).await.status,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(app.get(&uri).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn moves_an_avatar_only_to_a_student_without_one() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    let bruno = app.create_student("Bruno", &course_id).await;
+    let carla = app.create_student("Carla", &course_id).await;
+    let avatar_id = app.create_avatar("Nebula", &ana).await;
+    app.create_avatar("Quasar", &bruno).await;
+    let uri = format!("/api/v1/avatars/{avatar_id}");
+
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(json!({ "name": "Nebula", "student": bruno })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "Student already has an avatar!"
+    );
+
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(json!({ "name": "Nebula", "student": carla })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["student_id"], carla.as_str());
+}
+
+#[tokio::test]
+async fn renders_the_avatar_form_again_with_the_field_errors() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .post_form("/avatar/create", &format!("name=&student={ana}"))
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{ana}" selected>"#)));
+    assert!(response.body.contains("is required"));
+
+    // A student trashed after the form loaded is reported next to its field.
+    let response = app
+        .post_form("/avatar/create", "name=Nebula&student=unknown")
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Nebula""#));
+    assert!(response
+        .body
+        .contains(r#"<span class="field-error">is not an existing student</span>"#));
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/avatars",
+            Some(json!({ "name": "Nebula", "student": "" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json()["error"]["fields"]["student"], "is required");
+    assert_eq!(app.get("/api/v1/avatars").await.json()["total"], 0);
+}
+
+#[tokio::test]
+async fn shows_edits_and_deletes_an_avatar_through_the_html_pages() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    let bruno = app.create_student("Bruno", &course_id).await;
+    let avatar_id = app.create_avatar("Nebula", &ana).await;
+    app.create_avatar("Quasar", &bruno).await;
+
+    let response = app.get(&format!("/avatar/{avatar_id}")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Nebula"));
+    assert!(response.body.contains("Ana"));
+
+    let response = app.get(&format!("/avatar/{avatar_id}/edit")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Ana"));
+    assert!(!response.body.contains("Bruno"));
+
+    let response = app
+        .post_form(
+            &format!("/avatar/{avatar_id}/edit"),
+            &format!("name=Pulsar&student={ana}"),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert!(app
+        .get(&format!("/avatar/{avatar_id}"))
+        .await
+        .body
+        .contains("Pulsar"));
+
+    let response = app
+        .post_form(
+            &format!("/avatar/{avatar_id}/edit"),
+            &format!("name=Pulsar&student={bruno}"),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(response.body.contains("Student already has an avatar!"));
+
+    let response = app
+        .post_form(
+            &format!("/avatar/{avatar_id}/edit"),
+            "name=Pulsar&student=unknown",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Pulsar""#));
+    assert!(response.body.contains("is not an existing student"));
+
+    let response = app
+        .request(Method::DELETE, &format!("/avatar/{avatar_id}"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/avatar/{avatar_id}")).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,133 @@
+use std::{str::FromStr, time::Duration};
+
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+
+use crate::config::{postgres_url, Config, ConfigArgs};
+
+fn args() -> ConfigArgs {
+    ConfigArgs {
+        database_url: Some("postgres://user:secret@db:5432/school".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn command_line_flags_override_defaults() {
+    let config = Config::load(&ConfigArgs {
+        listen_addr: Some("127.0.0.1:8080".to_string()),
+        db_max_connections: Some(20),
+        db_idle_timeout_secs: Some(5),
+        log_level: Some("DEBUG".to_string()),
+        shutdown_timeout_secs: Some(3),
+        trash_retention_days: Some(7),
+        ..args()
+    })
+    .unwrap();
+
+    assert_eq!(config.listen_addr.to_string(), "127.0.0.1:8080");
+    assert_eq!(config.database.url, "postgres://user:secret@db:5432/school");
+    assert_eq!(config.database.max_connections, 20);
+    assert_eq!(config.database.idle_timeout, Duration::from_secs(5));
+    assert_eq!(config.log_level, "debug");
+    // The sslmode of the URL is kept unless one is configured.
+    assert!(config.database.ssl_mode.is_none());
+    assert_eq!(config.shutdown_timeout, Duration::from_secs(3));
+    assert_eq!(
+        config.trash_retention,
+        Duration::from_secs(7 * 24 * 60 * 60)
+    );
+}
+
+#[test]
+fn rejects_invalid_values_naming_the_offending_key() {
+    let err = Config::load(&ConfigArgs {
+        listen_addr: Some("localhost".to_string()),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("listen_addr"));
+
+    let err = Config::load(&ConfigArgs {
+        db_min_connections: Some(10),
+        db_max_connections: Some(2),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("database.min_connections"));
+
+    let err = Config::load(&ConfigArgs {
+        db_ssl_mode: Some("sometimes".to_string()),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("database.ssl_mode"));
+
+    let err = Config::load(&ConfigArgs {
+        database_url: Some("mysql://localhost/school".to_string()),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("database.url"));
+
+    let err = Config::load(&ConfigArgs {
+        db_connect_attempts: Some(0),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("database.connect_attempts"));
+
+    let err = Config::load(&ConfigArgs {
+        db_acquire_timeout_secs: Some(0),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("database.acquire_timeout_secs"));
+
+    let err = Config::load(&ConfigArgs {
+        trash_retention_days: Some(0),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("trash_retention_days"));
+
+    let err = Config::load(&ConfigArgs {
+        trash_retention_days: Some(u64::MAX),
+        ..args()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("trash_retention_days"));
+}
+
+#[test]
+fn reads_the_toml_file() {
+    let path = std::env::temp_dir().join(format!("config-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "listen_addr = \"127.0.0.1:4000\"\n\n[database]\nmax_connections = 12\nssl_mode = \"disable\"\n",
+    )
+    .unwrap();
+
+    let config = Config::load(&ConfigArgs {
+        config: Some(path.clone()),
+        ..args()
+    })
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(config.listen_addr.to_string(), "127.0.0.1:4000");
+    assert_eq!(config.database.max_connections, 12);
+    assert!(matches!(config.database.ssl_mode, Some(PgSslMode::Disable)));
+}
+
+#[test]
+fn encodes_the_postgres_variables_into_the_url() {
+    let url = postgres_url("app user", "p@ss:w/rd#1", "db", "5432", "school-2024");
+    assert_eq!(
+        url,
+        "postgres://app%20user:p%40ss%3Aw%2Frd%231@db:5432?dbname=school%2D2024"
+    );
+
+    let options = PgConnectOptions::from_str(&url).unwrap();
+    assert_eq!(options.get_username(), "app user");
+    assert_eq!(options.get_database(), Some("school-2024"));
+}
@@ -0,0 +1,282 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+
+#[tokio::test]
+async fn creates_lists_and_shows_courses() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app.get("/api/v1/courses").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["items"][0]["name"], "Computer Science");
+
+    let response = app.get(&format!("/api/v1/courses/{course_id}")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["id"], course_id.as_str());
+
+    let response = app.get("/courses").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Computer Science"));
+}
+
+#[tokio::test]
+async fn updates_an_existing_course_only() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/courses/{course_id}"),
+            Some(json!({ "name": "Software Engineering", "version": 1 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["name"], "Software Engineering");
+    assert_eq!(response.json()["version"], 2);
+
+    let response = app
+        .request(
+            Method::PUT,
+            "/api/v1/courses/unknown",
+            Some(json!({ "name": "Ghost", "version": 1 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.json()["error"]["status"], 404);
+
+    let response = app
+        .post_form("/course/unknown/edit", "name=Ghost&version=1")
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/api/v1/courses").await;
+    assert_eq!(response.json()["total"], 1);
+}
+
+#[tokio::test]
+async fn refuses_an_edit_made_against_a_stale_version() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app.get(&format!("/course/{course_id}/edit")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response
+        .body
+        .contains(r#"<input type="hidden" name="version" value="1" />"#));
+
+    let response = app
+        .post_form(
+            &format!("/course/{course_id}/edit"),
+            "name=Software+Engineering&version=1",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let response = app
+        .post_form(
+            &format!("/course/{course_id}/edit"),
+            "name=Information+Systems&version=1",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app.get(&format!("/api/v1/courses/{course_id}")).await;
+    assert_eq!(response.json()["name"], "Software Engineering");
+}
+
+#[tokio::test]
+async fn refuses_an_edit_without_a_version() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .post_form(
+            &format!("/course/{course_id}/edit"),
+            "name=Software+Engineering",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains("Version is required"));
+    assert!(response.body.contains(r#"value="Software Engineering""#));
+    assert!(response
+        .body
+        .contains(r#"<input type="hidden" name="version" value="1" />"#));
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/courses/{course_id}"),
+            Some(json!({ "name": "Software Engineering" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["error"]["fields"],
+        json!({ "version": "is required" })
+    );
+
+    let response = app.get(&format!("/api/v1/courses/{course_id}")).await;
+    assert_eq!(response.json()["name"], "Computer Science");
+}
+
+#[tokio::test]
+async fn keeps_the_version_when_the_edit_form_is_invalid() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .post_form(&format!("/course/{course_id}/edit"), "name=+&version=1")
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains("is required"));
+    assert!(response
+        .body
+        .contains(r#"<input type="hidden" name="version" value="1" />"#));
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/courses",
+            Some(json!({ "name": "x".repeat(101) })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["error"]["fields"]["name"],
+        "must have at most 100 characters"
+    );
+    assert_eq!(app.get("/api/v1/courses").await.json()["total"], 1);
+}
+
+#[tokio::test]
+async fn refuses_a_course_name_already_in_use_whatever_its_case() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/courses",
+            Some(json!({ "name": "computer science" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "Name is already used by another course"
+    );
+
+    let response = app
+        .post_form("/course/create", "name=COMPUTER+SCIENCE")
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(&format!(
+        r#"name already used by <a href="/course/{course_id}">Computer Science</a>"#
+    )));
+
+    // Saving a course under its own name is not a conflict.
+    let response = app
+        .post_form(
+            &format!("/course/{course_id}/edit"),
+            "name=Computer+Science&version=1",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+}
+
+#[tokio::test]
+async fn refuses_to_delete_a_course_with_enrolled_students() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/courses/{course_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let empty_course_id = app.create_course("Empty").await;
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/courses/{empty_course_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn renders_not_found_page_for_unknown_course() {
+    let app = TestApp::new();
+
+    let response = app.get("/course/unknown").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert!(response.body.contains("Course does not exists"));
+
+    let response = app.get("/course/unknown/edit").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.request(Method::DELETE, "/course/unknown", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn shows_enrolled_students_curriculum_and_avatar_coverage() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let other_course_id = app.create_course("Software Engineering").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    app.create_student("Bruno", &course_id).await;
+    app.create_student("Carla", &other_course_id).await;
+    app.create_avatar("Ninja", &ana).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({
+                "code": "IMD0001",
+                "name": "Algorithms",
+                "program": "Sorting",
+                "courses": [course_id],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let response = app.get(&format!("/course/{course_id}")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Enrolled students: 2"));
+    assert!(response.body.contains("Subjects: 1"));
+    assert!(response.body.contains("1 of 2 students (50%)"));
+    assert!(response.body.contains("Ninja"));
+    assert!(response.body.contains("IMD0001 - Algorithms"));
+    assert!(!response.body.contains("Carla"));
+    assert!(response
+        .body
+        .contains(&format!("/student/create?course={course_id}")));
+
+    let response = app
+        .get(&format!("/student/create?course={course_id}"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{course_id}" selected>"#)));
+
+    let response = app
+        .get(&format!("/subject/create?course={course_id}"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{course_id}" selected>"#)));
+}
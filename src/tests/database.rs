@@ -0,0 +1,87 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::http::StatusCode;
+use sqlx::postgres::PgSslMode;
+
+use super::TestApp;
+use crate::{
+    avatar,
+    config::DatabaseConfig,
+    course, health,
+    infra::{self, db::DatabaseStatus, memory},
+    language, operating_system,
+    state::AppState,
+    student, subject, trash,
+    view::Views,
+};
+
+/// In-memory state whose startup connection has not succeeded yet.
+pub fn connecting_state() -> AppState {
+    let database = memory::Database::new();
+
+    AppState::new(
+        Arc::new(student::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(course::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(subject::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(avatar::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(operating_system::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(language::repository::InMemoryRepository::new(
+            database.clone(),
+        )),
+        Arc::new(trash::repository::InMemoryRepository::new(database.clone())),
+        Arc::new(health::repository::InMemoryRepository::new(database)),
+        Views::new("src/view"),
+    )
+}
+
+#[tokio::test]
+async fn answers_service_unavailable_until_the_database_is_ready() {
+    let state = connecting_state();
+    let app = TestApp::with_state(state.clone());
+
+    let response = app.get("/students").await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.body.contains("Database unavailable"));
+
+    let response = app.get("/api/v1/courses").await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json()["error"]["message"], "Database unavailable");
+
+    let response = app.get("/assets/app.css").await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    state.database.set_ready();
+    let response = app.get("/students").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn gives_up_on_the_database_after_the_configured_attempts() {
+    let config = DatabaseConfig {
+        url: "postgres://postgres@127.0.0.1:1/school".to_string(),
+        min_connections: 0,
+        max_connections: 1,
+        idle_timeout: Duration::from_secs(1),
+        acquire_timeout: Duration::from_secs(1),
+        ssl_mode: Some(PgSslMode::Disable),
+        connect_attempts: 3,
+        connect_backoff: Duration::ZERO,
+        connect_backoff_max: Duration::ZERO,
+    };
+
+    let status = DatabaseStatus::default();
+    assert!(infra::db::wait_for_database(&config, &status)
+        .await
+        .is_err());
+    assert!(status.last_error().unwrap().contains("refused"));
+}
@@ -0,0 +1,51 @@
+use axum::http::StatusCode;
+
+use super::{database::connecting_state, TestApp};
+use crate::infra::migrations;
+
+#[tokio::test]
+async fn reports_liveness_readiness_and_status() {
+    let app = TestApp::new();
+
+    let response = app.get("/healthz").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["status"], "ok");
+
+    let response = app.get("/readyz").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let checks = response.json()["checks"].clone();
+    let names: Vec<&str> = checks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| check["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["database", "migrations", "templates"]);
+
+    let response = app.get("/status").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(body["database"]["ready"], true);
+    assert_eq!(
+        body["migrations"]["expected"],
+        migrations::latest_version().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn is_not_ready_while_the_database_is_unavailable() {
+    let app = TestApp::with_state(connecting_state());
+
+    let response = app.get("/healthz").await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get("/readyz").await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    let body = response.json();
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["checks"][0]["error"], "database unavailable");
+
+    let response = app.get("/status").await;
+    assert_eq!(response.json()["database"]["ready"], false);
+}
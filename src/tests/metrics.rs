@@ -0,0 +1,52 @@
+use axum::http::{Method, StatusCode};
+
+use super::TestApp;
+
+#[tokio::test]
+async fn exposes_request_and_entity_metrics() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    app.create_student("Ana", &course_id).await;
+    app.get("/students").await;
+    app.get("/student/unknown").await;
+
+    let response = app.get("/metrics").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.headers["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+
+    let body = response.body;
+    assert!(body.contains(r#"http_requests_total{method="GET",route="/students",status="200"} 1"#));
+    assert!(body.contains(
+        r#"http_requests_total{method="GET",route="/student/:student_id",status="404"} 1"#
+    ));
+    assert!(body.contains(
+        r#"http_request_duration_seconds_count{method="POST",route="/api/v1/courses"} 1"#
+    ));
+    assert!(body.contains(r#"entities_total{entity="course"} 1"#));
+    assert!(body.contains(r#"entities_total{entity="student"} 1"#));
+    assert!(body.contains(r#"entities_total{entity="avatar"} 0"#));
+    assert!(body.contains(r#"db_pool_connections{state="max"}"#));
+}
+
+#[tokio::test]
+async fn leaves_trashed_records_out_of_the_entity_counts() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/students/{student_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let body = app.get("/metrics").await.body;
+    assert!(body.contains(r#"entities_total{entity="student"} 0"#));
+    assert!(body.contains(r#"entities_total{entity="course"} 1"#));
+}
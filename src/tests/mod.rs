@@ -0,0 +1,149 @@
+mod avatar;
+mod config;
+mod course;
+mod database;
+mod health;
+mod metrics;
+mod operating_system;
+mod pagination;
+mod shutdown;
+mod student;
+mod subject;
+mod telemetry;
+mod trash;
+mod view;
+
+use std::path::Path;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::{infra::memory, router, state::AppState};
+
+pub struct TestApp {
+    router: Router,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub location: Option<String>,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).expect("response body is not JSON")
+    }
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        TestApp::with_state(AppState::in_memory(memory::Database::new()))
+    }
+
+    pub fn with_state(state: AppState) -> Self {
+        TestApp {
+            router: router(state, Path::new("assets")),
+        }
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .map(|value| value.to_str().unwrap().to_string());
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+
+        TestResponse {
+            status,
+            location,
+            headers,
+            body: String::from_utf8(bytes.to_vec()).unwrap(),
+        }
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.request(Method::GET, uri, None).await
+    }
+
+    pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> TestResponse {
+        let request = Request::builder().method(method).uri(uri);
+
+        let request = match body {
+            None => request.body(Body::empty()),
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+        };
+
+        self.send(request.unwrap()).await
+    }
+
+    pub async fn post_form(&self, uri: &str, form: &str) -> TestResponse {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(form.to_string()))
+            .unwrap();
+
+        self.send(request).await
+    }
+
+    pub async fn create_course(&self, name: &str) -> String {
+        let response = self
+            .request(
+                Method::POST,
+                "/api/v1/courses",
+                Some(json!({ "name": name })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        response.json()["id"].as_str().unwrap().to_string()
+    }
+
+    pub async fn create_student(&self, first_name: &str, course_id: &str) -> String {
+        let response = self
+            .request(
+                Method::POST,
+                "/api/v1/students",
+                Some(json!({
+                    "first_name": first_name,
+                    "last_name": "Silva",
+                    "course": course_id,
+                    "email": format!("{}@imd.ufrn.br", first_name.to_lowercase()),
+                    "language": "pt-BR",
+                    "operational_systems": ["Linux", "Windows"],
+                })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        response.json()["id"].as_str().unwrap().to_string()
+    }
+
+    pub async fn create_avatar(&self, name: &str, student_id: &str) -> String {
+        let response = self
+            .request(
+                Method::POST,
+                "/api/v1/avatars",
+                Some(json!({ "name": name, "student": student_id })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        response.json()["id"].as_str().unwrap().to_string()
+    }
+}
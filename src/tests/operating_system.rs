@@ -0,0 +1,146 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+
+#[tokio::test]
+async fn manages_operating_systems_through_the_html_pages() {
+    let app = TestApp::new();
+
+    let response = app.get("/operating-systems").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("macOS"));
+
+    let response = app
+        .post_form("/operating-system/create", "name=FreeBSD")
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.location.as_deref(), Some("/operating-systems"));
+
+    let response = app
+        .post_form("/operating-system/create", "name=freebsd")
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains("name already used by"));
+
+    let response = app.post_form("/operating-system/create", "name=+").await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains("is required"));
+
+    let names: Vec<_> = app.get("/api/v1/operating-systems").await.json()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|os| os["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["FreeBSD", "Linux", "macOS", "Windows"]);
+
+    let response = app.get("/student/create").await;
+    assert!(response.body.contains(r#"value="FreeBSD""#));
+}
+
+#[tokio::test]
+async fn renames_an_operating_system_for_its_students() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/operating-systems",
+            Some(json!({ "name": "GNU/Linux" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let linux = app.get("/api/v1/operating-systems").await.json()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|os| os["name"] == "Linux")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/operating-systems/{linux}"),
+            Some(json!({ "name": "gnu/linux" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "Name is already used by another operating system"
+    );
+
+    let response = app
+        .post_form(&format!("/operating-system/{linux}/edit"), "name=Tux")
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let body = app
+        .get(&format!("/api/v1/students/{student_id}"))
+        .await
+        .json();
+    assert_eq!(
+        body["student"]["operational_systems"],
+        json!(["Tux", "Windows"])
+    );
+
+    let response = app.get("/students/group-by?entity=os").await;
+    assert!(response.body.contains("Tux"));
+}
+
+#[tokio::test]
+async fn deletes_an_operating_system_only_when_no_student_uses_it() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let ids: Vec<(String, String)> = app.get("/api/v1/operating-systems").await.json()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|os| {
+            (
+                os["name"].as_str().unwrap().to_string(),
+                os["id"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let id_of = |name: &str| &ids.iter().find(|(n, _)| n == name).unwrap().1;
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/operating-system/{}", id_of("macOS")),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    // Trashed students still hold their operating systems, they may be restored.
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/students/{student_id}"),
+        None,
+    )
+    .await;
+    let linux = format!("/api/v1/operating-systems/{}", id_of("Linux"));
+    let response = app.request(Method::DELETE, &linux, None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/trash/student/{student_id}"),
+        None,
+    )
+    .await;
+    let response = app.request(Method::DELETE, &linux, None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get(&linux).await.status, StatusCode::NOT_FOUND);
+}
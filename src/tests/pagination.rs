@@ -0,0 +1,204 @@
+use axum::http::{Method, StatusCode};
+
+use super::TestApp;
+
+#[tokio::test]
+async fn pages_through_courses_with_a_cursor() {
+    let app = TestApp::new();
+    for name in [
+        "Physics",
+        "Biology",
+        "Chemistry",
+        "Computer Science",
+        "Math",
+    ] {
+        app.create_course(name).await;
+    }
+
+    let first = app.get("/api/v1/courses?per_page=2").await.json();
+    assert_eq!(first["total"], 5);
+    assert_eq!(first["total_pages"], 3);
+    assert_eq!(first["items"][0]["name"], "Biology");
+    assert_eq!(first["items"][1]["name"], "Chemistry");
+
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second = app
+        .get(&format!(
+            "/api/v1/courses?per_page=2&page=2&cursor={cursor}"
+        ))
+        .await
+        .json();
+    assert_eq!(second["page"], 2);
+    assert_eq!(second["items"][0]["name"], "Computer Science");
+    assert_eq!(second["items"][1]["name"], "Math");
+
+    let last = app.get("/api/v1/courses?per_page=2&page=3").await.json();
+    assert_eq!(last["items"].as_array().unwrap().len(), 1);
+    assert_eq!(last["items"][0]["name"], "Physics");
+    assert!(last["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn pages_back_to_the_rows_before_the_first_one_shown() {
+    let app = TestApp::new();
+    for name in [
+        "Biology",
+        "Chemistry",
+        "Computer Science",
+        "Math",
+        "Physics",
+    ] {
+        app.create_course(name).await;
+    }
+
+    let first = app.get("/api/v1/courses?per_page=2").await.json();
+    assert!(first["prev_cursor"].is_null());
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second = app
+        .get(&format!(
+            "/api/v1/courses?per_page=2&page=2&cursor={cursor}"
+        ))
+        .await
+        .json();
+    let before = second["prev_cursor"].as_str().unwrap();
+    assert_eq!(before, second["items"][0]["id"].as_str().unwrap());
+
+    // A course added ahead of the list does not shift the previous page.
+    app.create_course("Anatomy").await;
+    let previous = app
+        .get(&format!(
+            "/api/v1/courses?per_page=2&page=1&before={before}"
+        ))
+        .await
+        .json();
+    assert_eq!(previous["items"][0]["name"], "Biology");
+    assert_eq!(previous["items"][1]["name"], "Chemistry");
+    assert!(previous["prev_cursor"].is_string());
+
+    let response = app
+        .get(&format!("/api/v1/courses?cursor={cursor}&before={before}"))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get("/api/v1/courses?before=unknown").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn rejects_an_invalid_page_size() {
+    let app = TestApp::new();
+
+    let response = app.get("/api/v1/courses?per_page=0").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get("/courses?per_page=1000").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Parameters that do not parse keep the JSON error format of the API.
+    for uri in [
+        "/api/v1/students?page=abc",
+        "/api/v1/courses?per_page=-",
+        "/api/v1/subjects?page=abc",
+        "/api/v1/avatars?page=abc",
+        "/api/v1/operating-systems?page=abc",
+    ] {
+        let response = app.get(uri).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(response.json()["error"]["status"], 400, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn rejects_a_student_cursor_that_is_unknown_or_trashed() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    app.create_student("Bruno", &course_id).await;
+
+    let response = app
+        .get(&format!("/api/v1/students?per_page=1&page=2&cursor={ana}"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json()["items"][0]["student"]["first_name"],
+        "Bruno"
+    );
+
+    let response = app.get("/api/v1/students?cursor=unknown").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "cursor is not a live student"
+    );
+
+    app.request(Method::DELETE, &format!("/api/v1/students/{ana}"), None)
+        .await;
+    let response = app
+        .get(&format!("/api/v1/students?per_page=1&page=2&cursor={ana}"))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn rejects_unknown_or_trashed_cursors_on_every_list() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    for uri in [
+        "/api/v1/courses",
+        "/api/v1/subjects",
+        "/api/v1/avatars",
+        "/api/v1/operating-systems",
+        "/courses",
+    ] {
+        let response = app.get(&format!("{uri}?cursor=unknown")).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{uri}");
+    }
+
+    let response = app.get("/api/v1/courses?cursor=unknown").await;
+    assert_eq!(
+        response.json()["error"]["message"],
+        "cursor is not a live course"
+    );
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/courses/{course_id}"),
+        None,
+    )
+    .await;
+    let response = app
+        .get(&format!("/api/v1/courses?page=2&cursor={course_id}"))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn renders_pagination_controls_on_list_pages() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    let bruno = app.create_student("Bruno", &course_id).await;
+
+    let response = app.get("/students?per_page=1").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Page 1 of 2 (2 total)"));
+    assert!(response.body.contains("Ana"));
+    assert!(!response.body.contains("Bruno"));
+    assert!(response.body.contains("Next"));
+    assert!(!response.body.contains("Previous"));
+
+    let response = app
+        .get(&format!("/students?per_page=1&page=2&cursor={ana}"))
+        .await;
+    assert!(response.body.contains("Bruno"));
+    assert!(response
+        .body
+        .contains(&format!("before={bruno}&page=1&per_page=1")));
+
+    let response = app
+        .get(&format!("/students?per_page=1&page=1&before={bruno}"))
+        .await;
+    assert!(response.body.contains("Ana"));
+    assert!(!response.body.contains("Bruno"));
+}
@@ -0,0 +1,15 @@
+use super::TestApp;
+use crate::{infra::memory, state::AppState};
+
+#[tokio::test]
+async fn tracks_served_and_in_flight_requests() {
+    let state = AppState::in_memory(memory::Database::new());
+    let app = TestApp::with_state(state.clone());
+
+    app.get("/").await;
+    app.get("/students").await;
+    app.get("/assets/app.css").await;
+
+    assert_eq!(state.requests.completed(), 3);
+    assert_eq!(state.requests.in_flight(), 0);
+}
@@ -0,0 +1,509 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+
+#[tokio::test]
+async fn creates_and_shows_a_student_with_its_course_subjects() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    app.request(
+        Method::POST,
+        "/api/v1/subjects",
+        Some(json!({ "code": "IMD0001", "name": "Algorithms", "program": "Sorting", "courses": [course_id] })),
+    )
+    .await;
+
+    let response = app.get(&format!("/api/v1/students/{student_id}")).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let body = response.json();
+    assert_eq!(body["student"]["first_name"], "Ana");
+    assert_eq!(body["course"]["id"], course_id.as_str());
+    assert_eq!(body["subjects"][0]["code"], "IMD0001");
+}
+
+#[tokio::test]
+async fn rejects_students_for_unknown_courses_and_malformed_payloads() {
+    let app = TestApp::new();
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/students",
+            Some(json!({
+                "first_name": "Ana",
+                "last_name": "Silva",
+                "course": "unknown",
+                "email": "ana@imd.ufrn.br",
+                "language": "pt-BR",
+                "operational_systems": [],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/students",
+            Some(json!({ "first_name": "Ana" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json()["error"]["status"], 422);
+}
+
+#[tokio::test]
+async fn creates_a_student_through_the_html_form() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .post_form(
+            "/student/create",
+            &format!(
+                "first_name=Bruno&last_name=Lima&course={course_id}&email=bruno%40imd.ufrn.br\
+                 &language=en&operational_systems=Linux&operational_systems=macOS"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.location.as_deref(), Some("/students"));
+
+    let response = app.get("/students").await;
+    assert!(response.body.contains("Bruno"));
+}
+
+#[tokio::test]
+async fn renders_the_student_form_again_with_the_field_errors() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .post_form(
+            "/student/create",
+            &format!(
+                "first_name=Bruno&course={course_id}&email=bruno.imd.ufrn.br\
+                 &language=Cobol&operational_systems=Linux&operational_systems=BeOS"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Bruno""#));
+    assert!(response.body.contains(r#"value="bruno.imd.ufrn.br""#));
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{course_id}" selected>"#)));
+    assert!(response.body.contains("is required"));
+    assert!(response.body.contains("is not a valid email"));
+
+    // The language and operating systems are checked against their tables once the other
+    // fields are valid.
+    let response = app
+        .post_form(
+            "/student/create",
+            &format!(
+                "first_name=Bruno&last_name=Lima&course={course_id}&email=bruno%40imd.ufrn.br\
+                 &language=Cobol&operational_systems=Linux&operational_systems=BeOS"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Lima""#));
+    assert!(response.body.contains(
+        "must be one of de, en, en-GB, en-US, es, fr, it, ja, pt, pt-BR, pt-PT, und, zh"
+    ));
+    assert!(response
+        .body
+        .contains("BeOS is not one of Linux, macOS, Windows"));
+
+    // A course trashed after the form loaded is reported next to its field.
+    let response = app
+        .post_form(
+            "/student/create",
+            "first_name=Bruno&last_name=Lima&course=unknown&email=bruno%40imd.ufrn.br&language=en",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Lima""#));
+    assert!(response
+        .body
+        .contains(r#"<span class="field-error">is not an existing course</span>"#));
+
+    assert_eq!(app.get("/api/v1/students").await.json()["total"], 0);
+}
+
+#[tokio::test]
+async fn rejects_invalid_student_fields_with_their_messages() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/students",
+            Some(json!({
+                "first_name": " ",
+                "last_name": "Silva",
+                "course": course_id,
+                "email": "ana@imd",
+                "language": "pt-BR",
+                "operational_systems": ["Linux"],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["error"]["fields"],
+        json!({ "first_name": "is required", "email": "is not a valid email" })
+    );
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{student_id}"),
+            Some(json!({ "operational_systems": ["Linux", "Solaris"] })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["error"]["fields"]["operational_systems"],
+        "Solaris is not one of Linux, macOS, Windows"
+    );
+}
+
+#[tokio::test]
+async fn refuses_an_email_already_used_by_another_student() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let ana = app.create_student("Ana", &course_id).await;
+    let bruno = app.create_student("Bruno", &course_id).await;
+
+    let response = app
+        .post_form(
+            "/student/create",
+            &format!(
+                "first_name=Ana&last_name=Souza&course={course_id}&email=ANA%40imd.ufrn.br\
+                 &language=en"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(&format!(
+        r#"email already used by <a href="/student/{ana}">Ana Silva</a>"#
+    )));
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{bruno}"),
+            Some(json!({ "email": "Ana@imd.ufrn.br" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "Email is already used by another student"
+    );
+
+    // A trashed student does not hold its email, but cannot come back while it is used.
+    app.request(Method::DELETE, &format!("/api/v1/students/{ana}"), None)
+        .await;
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{bruno}"),
+            Some(json!({ "email": "ana@imd.ufrn.br" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/trash/student/{ana}/restore"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn groups_students_by_course_language_and_os() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    app.create_student("Ana", &course_id).await;
+    app.create_course("Empty").await;
+
+    let response = app.get("/students/group-by?entity=course").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Computer Science"));
+    assert!(!response.body.contains("Empty"));
+
+    let response = app.get("/students/group-by?entity=os").await;
+    assert!(response.body.contains("Linux"));
+    assert!(response.body.contains("Windows"));
+
+    let response = app.get("/students/group-by?entity=language").await;
+    assert!(response.body.contains("Portuguese (Brazil)"));
+
+    let response = app.get("/students/group-by?entity=shoe-size").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn groups_languages_by_their_catalog_code() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    for (first_name, language) in [("Bruno", "pt_br"), ("Carla", " PT-br ")] {
+        let response = app
+            .request(
+                Method::POST,
+                "/api/v1/students",
+                Some(json!({
+                    "first_name": first_name,
+                    "last_name": "Lima",
+                    "course": course_id,
+                    "email": format!("{}@imd.ufrn.br", first_name.to_lowercase()),
+                    "language": language,
+                })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert_eq!(response.json()["language"], "pt-BR");
+    }
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{student_id}"),
+            Some(json!({ "language": "tlh" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json()["error"]["fields"]["language"],
+        "must be one of de, en, en-GB, en-US, es, fr, it, ja, pt, pt-BR, pt-PT, und, zh"
+    );
+
+    let body = app
+        .get(&format!("/api/v1/students/{student_id}"))
+        .await
+        .json();
+    assert_eq!(
+        body["language"],
+        json!({ "code": "pt-BR", "name": "Portuguese (Brazil)" })
+    );
+
+    let response = app.get("/students/group-by?entity=language").await;
+    assert_eq!(response.body.matches("<h2>").count(), 1);
+    assert!(response.body.contains("3 students in total"));
+
+    let languages = app.get("/api/v1/languages").await.json();
+    assert_eq!(
+        languages[2],
+        json!({ "code": "en-GB", "name": "English (United Kingdom)" })
+    );
+}
+
+#[tokio::test]
+async fn trashes_a_student_together_with_its_avatar() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    let avatar_id = app.create_avatar("Nebula", &student_id).await;
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/students/{student_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.get(&format!("/api/v1/avatars/{avatar_id}")).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .request(Method::DELETE, "/api/v1/students/unknown", None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn escapes_student_names_on_the_list_page() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    app.create_student("<script>alert(1)</script>", &course_id)
+        .await;
+
+    let response = app.get("/students").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response
+        .body
+        .contains("&lt;script&gt;alert(1)&lt;&#x2f;script&gt;"));
+    assert!(!response.body.contains("<script>alert(1)"));
+}
+
+#[tokio::test]
+async fn filters_and_sorts_the_student_list() {
+    let app = TestApp::new();
+    let computing = app.create_course("Computer Science").await;
+    let math = app.create_course("Math").await;
+    app.create_student("Carla", &computing).await;
+    app.create_student("Ana", &math).await;
+    app.create_student("Bruno", &computing).await;
+
+    let names = |body: serde_json::Value| -> Vec<String> {
+        body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["student"]["first_name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let response = app
+        .get(&format!("/api/v1/students?course={computing}"))
+        .await;
+    assert_eq!(names(response.json()), ["Bruno", "Carla"]);
+
+    let response = app
+        .get("/api/v1/students?name=RUN&email_domain=IMD.ufrn.br")
+        .await;
+    assert_eq!(names(response.json()), ["Bruno"]);
+
+    let response = app
+        .get("/api/v1/students?os=Linux&sort=course&order=desc")
+        .await;
+    assert_eq!(names(response.json()), ["Ana", "Carla", "Bruno"]);
+
+    let response = app.get("/api/v1/students?sort=created_at").await;
+    assert_eq!(names(response.json()), ["Carla", "Ana", "Bruno"]);
+
+    let response = app.get("/api/v1/students?sort=age").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn keeps_the_filters_across_pages_of_the_html_list() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    app.create_student("Ana", &course_id).await;
+    app.create_student("Bruno", &course_id).await;
+
+    let response = app
+        .get("/students?language=pt-BR&sort=email&per_page=1&name=")
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Page 1 of 2 (2 total)"));
+    assert!(response
+        .body
+        .contains("?language=pt-BR&amp;sort=email&amp;cursor="));
+}
+
+#[tokio::test]
+async fn edits_a_student_through_the_html_form() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let math_id = app.create_course("Math").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let response = app.get(&format!("/student/{student_id}/edit")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains(r#"value="ana@imd.ufrn.br""#));
+    assert!(response
+        .body
+        .contains(r#"<option value="pt-BR" selected>Portuguese (Brazil)</option>"#));
+
+    let response = app
+        .post_form(
+            &format!("/student/{student_id}/edit"),
+            &format!(
+                "first_name=Ana&last_name=Souza&course={math_id}&email=ana.souza%40imd.ufrn.br\
+                 &language=en&operational_systems=macOS"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.location.as_deref(),
+        Some(format!("/student/{student_id}").as_str())
+    );
+
+    let body = app
+        .get(&format!("/api/v1/students/{student_id}"))
+        .await
+        .json();
+    assert_eq!(body["student"]["email"], "ana.souza@imd.ufrn.br");
+    assert_eq!(body["student"]["operational_systems"], json!(["macOS"]));
+    assert_eq!(body["course"]["id"], math_id.as_str());
+}
+
+#[tokio::test]
+async fn does_not_create_students_when_editing_unknown_ids() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app.get("/student/unknown/edit").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .post_form(
+            "/student/unknown/edit",
+            &format!(
+                "first_name=Ana&last_name=Souza&course={course_id}&email=ana%40imd.ufrn.br\
+                 &language=en"
+            ),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .request(
+            Method::PATCH,
+            "/api/v1/students/unknown",
+            Some(json!({ "first_name": "Ana" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/api/v1/students").await;
+    assert_eq!(response.json()["total"], 0);
+}
+
+#[tokio::test]
+async fn patches_only_the_given_student_fields() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{student_id}"),
+            Some(json!({ "email": "ana@ufrn.br" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let body = response.json();
+    assert_eq!(body["email"], "ana@ufrn.br");
+    assert_eq!(body["first_name"], "Ana");
+    assert_eq!(body["operational_systems"], json!(["Linux", "Windows"]));
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("/api/v1/students/{student_id}"),
+            Some(json!({ "course": "unknown" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,286 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+
+#[tokio::test]
+async fn creates_and_relinks_subject_courses() {
+    let app = TestApp::new();
+    let first_course = app.create_course("Computer Science").await;
+    let second_course = app.create_course("Software Engineering").await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({
+                "code": "IMD0001",
+                "name": "Algorithms",
+                "program": "Sorting",
+                "courses": [first_course],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let subject_id = response.json()["id"].as_str().unwrap().to_string();
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/subjects/{subject_id}"),
+            Some(json!({
+                "code": "IMD0001",
+                "name": "Algorithms I",
+                "program": "Sorting",
+                "courses": [second_course],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get(&format!("/api/v1/subjects/{subject_id}")).await;
+    let body = response.json();
+    assert_eq!(body["subject"]["name"], "Algorithms I");
+    assert_eq!(body["courses"].as_array().unwrap().len(), 1);
+    assert_eq!(body["courses"][0]["id"], second_course.as_str());
+
+    let response = app.get("/subjects").await;
+    assert!(response.body.contains("Software Engineering"));
+}
+
+#[tokio::test]
+async fn lists_subjects_that_are_not_linked_to_any_course() {
+    let app = TestApp::new();
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({ "code": "IMD0001", "name": "Algorithms", "program": "Sorting" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let body = app.get("/api/v1/subjects").await.json();
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["subject"]["code"], "IMD0001");
+    assert_eq!(body["items"][0]["courses"], json!([]));
+
+    let response = app.get("/subjects").await;
+    assert!(response.body.contains("IMD0001"));
+    assert!(response.body.contains("Not linked to any course"));
+}
+
+#[tokio::test]
+async fn rejects_subjects_linked_to_unknown_courses() {
+    let app = TestApp::new();
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({
+                "code": "IMD0001",
+                "name": "Algorithms",
+                "program": "Sorting",
+                "courses": ["unknown"],
+            })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn refuses_a_subject_code_already_used_with_the_same_program() {
+    let app = TestApp::new();
+    let create_subject = |code: &'static str, program: &'static str| {
+        app.request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({ "code": code, "name": "Algorithms", "program": program })),
+        )
+    };
+
+    let response = create_subject("IMD0001", "Sorting").await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let subject_id = response.json()["id"].as_str().unwrap().to_string();
+
+    let response = create_subject("imd0001", "Sorting").await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.json()["error"]["message"],
+        "Code is already used by another subject with the same program"
+    );
+
+    let response = create_subject("IMD0001", "Graphs").await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let response = app
+        .post_form(
+            "/subject/create",
+            "code=Imd0001&name=Algorithms+II&program=Sorting",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(&format!(
+        r#"code already used by <a href="/subject/{subject_id}">IMD0001 - Algorithms</a>"#
+    )));
+}
+
+#[tokio::test]
+async fn renders_the_subject_form_again_with_the_field_errors() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .post_form(
+            "/subject/create",
+            &format!("code=&name=Algorithms&program=Sorting&courses={course_id}"),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Algorithms""#));
+    assert!(response.body.contains(">Sorting</textarea>"));
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{course_id}" selected>"#)));
+    assert!(response
+        .body
+        .contains(r#"<span class="field-error">is required</span>"#));
+
+    // Fields left out of the form are reported like empty ones.
+    let response = app.post_form("/subject/create", "code=IMD0001").await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="IMD0001""#));
+    assert_eq!(response.body.matches("is required").count(), 2);
+
+    let response = app
+        .post_form(
+            "/subject/create",
+            "code=IMD0001&name=Algorithms&program=Sorting&courses=unknown",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body.contains(r#"value="Algorithms""#));
+    assert!(response
+        .body
+        .contains(r#"<span class="field-error">unknown is not an existing course</span>"#));
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({ "code": "", "name": "" })),
+        )
+        .await;
+    assert_eq!(
+        response.json()["error"]["fields"],
+        json!({ "code": "is required", "name": "is required", "program": "is required" })
+    );
+}
+
+#[tokio::test]
+async fn deletes_a_linked_subject_only_with_cascade() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({ "code": "IMD0001", "name": "Algorithms", "program": "Sorting", "courses": [course_id] })),
+        )
+        .await;
+    let subject_id = response.json()["id"].as_str().unwrap().to_string();
+
+    let uri = format!("/api/v1/subjects/{subject_id}");
+    let response = app.request(Method::DELETE, &uri, None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(app.get(&uri).await.status, StatusCode::OK);
+
+    assert_eq!(
+        app.request(Method::DELETE, &format!("{uri}?cascade=true"), None)
+            .await
+            .status,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(app.get(&uri).await.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/trash/subject/{subject_id}/restore"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get(&uri).await.json()["courses"], json!([]));
+    assert_eq!(
+        app.request(Method::DELETE, &uri, None).await.status,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[tokio::test]
+async fn shows_edits_and_deletes_a_subject_through_the_html_pages() {
+    let app = TestApp::new();
+    let computing = app.create_course("Computer Science").await;
+    let math = app.create_course("Math").await;
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/subjects",
+            Some(json!({ "code": "IMD0001", "name": "Algorithms", "program": "Sorting", "courses": [computing] })),
+        )
+        .await;
+    let subject_id = response.json()["id"].as_str().unwrap().to_string();
+
+    let response = app.get(&format!("/subject/{subject_id}")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Computer Science"));
+
+    let response = app.get(&format!("/subject/{subject_id}/edit")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response
+        .body
+        .contains(&format!(r#"<option value="{computing}" selected>"#)));
+
+    let response = app
+        .post_form(
+            &format!("/subject/{subject_id}/edit"),
+            &format!("code=IMD0002&name=Algorithms+II&program=Graphs&courses={math}"),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let body = app
+        .get(&format!("/api/v1/subjects/{subject_id}"))
+        .await
+        .json();
+    assert_eq!(body["subject"]["code"], "IMD0002");
+    assert_eq!(
+        body["courses"],
+        json!([{ "id": math, "name": "Math", "version": 1 }])
+    );
+
+    let response = app
+        .post_form(
+            &format!("/subject/{subject_id}/edit"),
+            "code=IMD0002&name=Algorithms+II&program=Graphs",
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let response = app
+        .request(Method::DELETE, &format!("/subject/{subject_id}"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/subject/{subject_id}")).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let response = app
+        .post_form("/subject/unknown/edit", "code=X&name=Y&program=Z")
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
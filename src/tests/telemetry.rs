@@ -0,0 +1,35 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+
+use super::TestApp;
+
+#[tokio::test]
+async fn generates_a_request_id_for_every_response() {
+    let app = TestApp::new();
+
+    let first = app.get("/students").await;
+    let second = app.get("/api/v1/students/unknown").await;
+    assert_eq!(second.status, StatusCode::NOT_FOUND);
+
+    let first = first.headers["x-request-id"].to_str().unwrap().to_string();
+    let second = second.headers["x-request-id"].to_str().unwrap().to_string();
+    assert!(!first.is_empty());
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn propagates_the_request_id_sent_by_the_client() {
+    let app = TestApp::new();
+
+    let request = Request::builder()
+        .uri("/courses")
+        .header("x-request-id", "trace-me-42")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers["x-request-id"], "trace-me-42");
+}
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use super::TestApp;
+use crate::{infra::memory, state::AppState};
+
+#[tokio::test]
+async fn hides_deleted_records_until_they_are_restored() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    app.create_avatar("Nebula", &student_id).await;
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/students/{student_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(app.get("/api/v1/students").await.json()["total"], 0);
+    assert_eq!(app.get("/api/v1/avatars").await.json()["total"], 0);
+    let response = app.get("/students/group-by?entity=language").await;
+    assert!(!response.body.contains("Ana"));
+
+    let trash = app.get("/api/v1/trash").await.json();
+    assert_eq!(trash.as_array().unwrap().len(), 2);
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/trash/student/{student_id}/restore"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.get("/api/v1/avatars").await.json();
+    assert_eq!(response["items"][0]["avatar"]["fantasy_name"], "Nebula");
+    assert_eq!(app.get("/api/v1/trash").await.json(), json!([]));
+}
+
+#[tokio::test]
+async fn restores_a_student_only_once_its_course_is_back() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/students/{student_id}"),
+        None,
+    )
+    .await;
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/courses/{course_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let restore_student = format!("/api/v1/trash/student/{student_id}/restore");
+    let response = app.request(Method::POST, &restore_student, None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/trash/course/{course_id}/restore"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.request(Method::POST, &restore_student, None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.request(Method::POST, &restore_student, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn purges_a_course_only_after_its_students() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/students/{student_id}"),
+        None,
+    )
+    .await;
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/courses/{course_id}"),
+        None,
+    )
+    .await;
+
+    let purge_course = format!("/api/v1/trash/course/{course_id}");
+    let response = app.request(Method::DELETE, &purge_course, None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/v1/trash/student/{student_id}"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.request(Method::DELETE, &purge_course, None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get("/api/v1/trash").await.json(), json!([]));
+
+    let response = app
+        .request(Method::DELETE, "/api/v1/trash/unicorn/x", None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.json()["error"]["status"], 400);
+
+    let response = app
+        .request(Method::POST, "/api/v1/trash/unicorn/x/restore", None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.json()["error"]["status"], 400);
+}
+
+#[tokio::test]
+async fn purges_records_older_than_the_retention_period() {
+    let state = AppState::in_memory(memory::Database::new());
+    let app = TestApp::with_state(state.clone());
+    let course_id = app.create_course("Computer Science").await;
+    let student_id = app.create_student("Ana", &course_id).await;
+    app.create_avatar("Nebula", &student_id).await;
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/students/{student_id}"),
+        None,
+    )
+    .await;
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/courses/{course_id}"),
+        None,
+    )
+    .await;
+
+    let purged = state
+        .trash
+        .purge_expired(Duration::from_secs(60 * 60))
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+
+    // A retention reaching back before any representable date purges nothing.
+    let purged = state.trash.purge_expired(Duration::MAX).await.unwrap();
+    assert_eq!(purged, 0);
+
+    let purged = state.trash.purge_expired(Duration::ZERO).await.unwrap();
+    assert_eq!(purged, 3);
+    assert_eq!(app.get("/api/v1/trash").await.json(), json!([]));
+}
+
+#[tokio::test]
+async fn restores_from_the_trash_page() {
+    let app = TestApp::new();
+    let course_id = app.create_course("Computer Science").await;
+
+    let response = app
+        .request(Method::DELETE, &format!("/course/{course_id}"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get("/trash").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Computer Science"));
+    assert!(response
+        .body
+        .contains(&format!("/trash/course/{course_id}/restore")));
+
+    let response = app
+        .post_form(&format!("/trash/course/{course_id}/restore"), "")
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.location.as_deref(), Some("/trash"));
+
+    let response = app.get("/trash").await;
+    assert!(response.body.contains("The trash is empty"));
+    assert_eq!(
+        app.get(&format!("/course/{course_id}")).await.status,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,43 @@
+use axum::http::StatusCode;
+
+use super::TestApp;
+use crate::NAVIGATION;
+
+#[tokio::test]
+async fn renders_every_page_inside_the_base_layout_with_the_navigation() {
+    let app = TestApp::new();
+
+    for uri in [
+        "/",
+        "/students",
+        "/course/create",
+        "/subjects",
+        "/course/unknown/edit",
+    ] {
+        let response = app.get(uri).await;
+        assert!(response.body.contains(r#"<nav class="nav">"#), "{uri}");
+        assert!(response.body.contains("/assets/app.css"), "{uri}");
+
+        for section in NAVIGATION {
+            assert!(response.body.contains(section.title), "{uri}");
+        }
+    }
+
+    let response = app.get("/").await;
+    assert_eq!(response.status, StatusCode::OK);
+    for link in NAVIGATION.iter().flat_map(|section| section.links) {
+        assert!(response.body.contains(link.label));
+    }
+}
+
+#[tokio::test]
+async fn serves_static_assets() {
+    let app = TestApp::new();
+
+    let response = app.get("/assets/app.js").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("function deleteResource"));
+
+    let response = app.get("/assets/missing.css").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
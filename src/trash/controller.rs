@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Redirect, Response},
+    Json,
+};
+use axum_extra::extract::WithRejection;
+
+use crate::{
+    custom::{HtmlResponse, JsonError},
+    error::AppResult,
+    state::AppState,
+};
+
+use super::model::{TrashKind, TrashedItem};
+
+pub async fn trash_html(State(state): State<AppState>) -> AppResult<Response> {
+    let items = state.trash.list().await?;
+
+    Ok(state
+        .views
+        .render_template("trash/list", items.into())?
+        .to_html_response())
+}
+
+pub async fn restore(
+    State(state): State<AppState>,
+    Path((kind, id)): Path<(TrashKind, String)>,
+) -> AppResult<Redirect> {
+    state.trash.restore(kind, &id).await?;
+
+    Ok(Redirect::to("/trash"))
+}
+
+pub async fn purge(
+    State(state): State<AppState>,
+    Path((kind, id)): Path<(TrashKind, String)>,
+) -> AppResult<StatusCode> {
+    state.trash.purge(kind, &id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_json(State(state): State<AppState>) -> Result<Json<Vec<TrashedItem>>, JsonError> {
+    Ok(Json(state.trash.list().await?))
+}
+
+pub async fn restore_json(
+    State(state): State<AppState>,
+    WithRejection(Path((kind, id)), _): WithRejection<Path<(TrashKind, String)>, JsonError>,
+) -> Result<StatusCode, JsonError> {
+    state.trash.restore(kind, &id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn purge_json(
+    State(state): State<AppState>,
+    WithRejection(Path((kind, id)), _): WithRejection<Path<(TrashKind, String)>, JsonError>,
+) -> Result<StatusCode, JsonError> {
+    state.trash.purge(kind, &id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
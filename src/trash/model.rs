@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Student,
+    Course,
+    Subject,
+    Avatar,
+}
+
+impl TrashKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrashKind::Student => "Student",
+            TrashKind::Course => "Course",
+            TrashKind::Subject => "Subject",
+            TrashKind::Avatar => "Avatar",
+        }
+    }
+}
+
+/// A deleted row of any entity, as listed on the trash page.
+#[derive(Clone, Debug, Serialize)]
+pub struct TrashedItem {
+    pub kind: TrashKind,
+    pub id: String,
+    pub label: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub deleted_at: OffsetDateTime,
+}
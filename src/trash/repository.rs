@@ -0,0 +1,564 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+use tracing::instrument;
+
+use super::model::{TrashKind, TrashedItem};
+use crate::error::{AppError, AppResult};
+#[cfg(test)]
+use crate::infra::memory;
+
+const COURSE_IS_TRASHED: &str = "The course of this student is in the trash, restore it first";
+const STUDENT_IS_TRASHED: &str = "The student of this avatar is in the trash, restore it first";
+const COURSE_HAS_STUDENTS: &str =
+    "Unable to purge the course because students are still enrolled, purge them first";
+
+fn not_in_trash(kind: TrashKind) -> AppError {
+    AppError::NotFound(format!("{} is not in the trash", kind.label()))
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// Every trashed row, the most recently deleted first.
+    async fn list(&self) -> AppResult<Vec<TrashedItem>>;
+
+    /// Takes the row out of the trash. A student comes back with the avatar that was trashed
+    /// together with it, and a subject deleted with its course links comes back unlinked. Fails
+    /// with `Conflict` while the course of a student or the student of an avatar is still in the
+    /// trash.
+    async fn restore(&self, kind: TrashKind, id: &str) -> AppResult<()>;
+
+    /// Deletes a trashed row for good, along with the avatar of a student and the course links
+    /// of a subject or course.
+    async fn purge(&self, kind: TrashKind, id: &str) -> AppResult<()>;
+
+    /// Purges every row trashed before `cutoff` and returns how many were deleted. Courses that
+    /// still have students are left for a later run.
+    async fn purge_deleted_before(&self, cutoff: OffsetDateTime) -> AppResult<u64>;
+}
+
+pub struct PostgresRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub fn new(database: Pool<Postgres>) -> Self {
+        PostgresRepository { database }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    #[instrument(level = "debug", skip_all, fields(query = "trash.list"))]
+    async fn list(&self) -> AppResult<Vec<TrashedItem>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT 'student' "kind!", id "id!", first_name || ' ' || last_name "label!", deleted_at "deleted_at!"
+            FROM student WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'course', id, name, deleted_at
+            FROM course WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'subject', id, code || ' - ' || name, deleted_at
+            FROM subject WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'avatar', id, fantasy_name, deleted_at
+            FROM avatar WHERE deleted_at IS NOT NULL
+            ORDER BY 4 DESC, 2
+            "#
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let kind = match row.kind.as_str() {
+                    "student" => TrashKind::Student,
+                    "course" => TrashKind::Course,
+                    "subject" => TrashKind::Subject,
+                    "avatar" => TrashKind::Avatar,
+                    kind => return Err(AppError::Internal(format!("Unknown trash kind {kind}"))),
+                };
+
+                Ok(TrashedItem {
+                    kind,
+                    id: row.id,
+                    label: row.label,
+                    deleted_at: row.deleted_at,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "trash.restore"))]
+    async fn restore(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        let restored = match kind {
+            TrashKind::Student => {
+                let student = sqlx::query!(
+                    r#"
+                    SELECT s.deleted_at "deleted_at!", c.deleted_at IS NOT NULL "course_trashed!"
+                    FROM student s
+                    INNER JOIN course c ON c.id = s.course_id
+                    WHERE s.id = $1 AND s.deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .fetch_optional(&self.database)
+                .await?
+                .ok_or_else(|| not_in_trash(kind))?;
+
+                if student.course_trashed {
+                    return Err(AppError::Conflict(COURSE_IS_TRASHED.to_string()));
+                }
+
+                let mut tx = self.database.begin().await?;
+
+                sqlx::query!(
+                    r#"
+                    UPDATE avatar SET deleted_at = NULL
+                    WHERE student_id = $1 AND deleted_at = $2
+                    "#,
+                    id,
+                    student.deleted_at
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                let restored = sqlx::query!(
+                    r#"
+                    UPDATE student SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                tx.commit().await?;
+                restored
+            }
+            TrashKind::Avatar => {
+                let student_trashed = sqlx::query_scalar!(
+                    r#"
+                    SELECT s.deleted_at IS NOT NULL "student_trashed!"
+                    FROM avatar a
+                    INNER JOIN student s ON s.id = a.student_id
+                    WHERE a.id = $1 AND a.deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .fetch_optional(&self.database)
+                .await?
+                .ok_or_else(|| not_in_trash(kind))?;
+
+                if student_trashed {
+                    return Err(AppError::Conflict(STUDENT_IS_TRASHED.to_string()));
+                }
+
+                sqlx::query!(
+                    r#"
+                    UPDATE avatar SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&self.database)
+                .await?
+            }
+            TrashKind::Course => {
+                sqlx::query!(
+                    r#"
+                    UPDATE course SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&self.database)
+                .await?
+            }
+            TrashKind::Subject => {
+                sqlx::query!(
+                    r#"
+                    UPDATE subject SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&self.database)
+                .await?
+            }
+        };
+
+        match restored.rows_affected() {
+            0 => Err(not_in_trash(kind)),
+            _ => Ok(()),
+        }
+    }
+
+    #[instrument(level = "debug", skip_all, fields(query = "trash.purge"))]
+    async fn purge(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        let mut tx = self.database.begin().await?;
+
+        let purged = match kind {
+            TrashKind::Student => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM avatar
+                    WHERE student_id = $1
+                        AND EXISTS (SELECT 1 FROM student WHERE id = $1 AND deleted_at IS NOT NULL)
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query!(
+                    r#"
+                    DELETE FROM student WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+            TrashKind::Course => {
+                let enrolled = sqlx::query_scalar!(
+                    r#"
+                    SELECT EXISTS (SELECT 1 FROM student WHERE course_id = $1) "enrolled!"
+                    "#,
+                    id
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+
+                if enrolled {
+                    return Err(AppError::Conflict(COURSE_HAS_STUDENTS.to_string()));
+                }
+
+                sqlx::query!(
+                    r#"
+                    DELETE FROM subject_course
+                    WHERE course_id = $1
+                        AND EXISTS (SELECT 1 FROM course WHERE id = $1 AND deleted_at IS NOT NULL)
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query!(
+                    r#"
+                    DELETE FROM course WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+            TrashKind::Subject => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM subject_course
+                    WHERE subject_id = $1
+                        AND EXISTS (SELECT 1 FROM subject WHERE id = $1 AND deleted_at IS NOT NULL)
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query!(
+                    r#"
+                    DELETE FROM subject WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+            TrashKind::Avatar => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM avatar WHERE id = $1 AND deleted_at IS NOT NULL
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?
+            }
+        };
+
+        if purged.rows_affected() == 0 {
+            return Err(not_in_trash(kind));
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(query = "trash.purge_deleted_before")
+    )]
+    async fn purge_deleted_before(&self, cutoff: OffsetDateTime) -> AppResult<u64> {
+        let mut tx = self.database.begin().await?;
+
+        let avatars = sqlx::query!(
+            r#"
+            DELETE FROM avatar
+            WHERE deleted_at < $1
+                OR student_id IN (SELECT id FROM student WHERE deleted_at < $1)
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM subject_course
+            WHERE subject_id IN (SELECT id FROM subject WHERE deleted_at < $1)
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let subjects = sqlx::query!(
+            r#"
+            DELETE FROM subject WHERE deleted_at < $1
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let students = sqlx::query!(
+            r#"
+            DELETE FROM student WHERE deleted_at < $1
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM subject_course
+            WHERE course_id IN (
+                SELECT id FROM course c
+                WHERE c.deleted_at < $1
+                    AND NOT EXISTS (SELECT 1 FROM student s WHERE s.course_id = c.id)
+            )
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let courses = sqlx::query!(
+            r#"
+            DELETE FROM course c
+            WHERE c.deleted_at < $1
+                AND NOT EXISTS (SELECT 1 FROM student s WHERE s.course_id = c.id)
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(avatars.rows_affected()
+            + subjects.rows_affected()
+            + students.rows_affected()
+            + courses.rows_affected())
+    }
+}
+
+#[cfg(test)]
+pub struct InMemoryRepository {
+    database: memory::Database,
+}
+
+#[cfg(test)]
+impl InMemoryRepository {
+    pub fn new(database: memory::Database) -> Self {
+        InMemoryRepository { database }
+    }
+
+    fn purge_row(tables: &mut memory::Tables, kind: TrashKind, id: &str) -> AppResult<()> {
+        let trashed = tables
+            .trashed(kind, id)
+            .cloned()
+            .ok_or_else(|| not_in_trash(kind))?;
+
+        match &trashed.row {
+            memory::TrashedRow::Student(student) => tables.trash.retain(
+                |t| !matches!(&t.row, memory::TrashedRow::Avatar(a) if a.student_id == student.id),
+            ),
+            memory::TrashedRow::Course(course) => {
+                let enrolled = tables.students.iter().any(|s| s.course_id == course.id)
+                    || tables.trash.iter().any(|t| {
+                        matches!(&t.row, memory::TrashedRow::Student(s) if s.course_id == course.id)
+                    });
+                if enrolled {
+                    return Err(AppError::Conflict(COURSE_HAS_STUDENTS.to_string()));
+                }
+
+                tables.subject_courses.retain(|(_, c)| *c != course.id);
+            }
+            memory::TrashedRow::Subject(subject) => {
+                tables.subject_courses.retain(|(s, _)| *s != subject.id)
+            }
+            memory::TrashedRow::Avatar(_) => {}
+        }
+
+        tables
+            .trash
+            .retain(|t| !(t.row.kind() == kind && t.row.id() == id));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn list(&self) -> AppResult<Vec<TrashedItem>> {
+        let tables = self.database.read();
+
+        let mut items: Vec<TrashedItem> = tables
+            .trash
+            .iter()
+            .map(|trashed| TrashedItem {
+                kind: trashed.row.kind(),
+                id: trashed.row.id().to_string(),
+                label: match &trashed.row {
+                    memory::TrashedRow::Student(s) => format!("{} {}", s.first_name, s.last_name),
+                    memory::TrashedRow::Course(c) => c.name.clone(),
+                    memory::TrashedRow::Subject(s) => format!("{} - {}", s.code, s.name),
+                    memory::TrashedRow::Avatar(a) => a.fantasy_name.clone(),
+                },
+                deleted_at: trashed.deleted_at,
+            })
+            .collect();
+        items.sort_by(|a, b| (b.deleted_at, &a.id).cmp(&(a.deleted_at, &b.id)));
+
+        Ok(items)
+    }
+
+    async fn restore(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        let mut tables = self.database.write();
+
+        let trashed = tables
+            .trashed(kind, id)
+            .cloned()
+            .ok_or_else(|| not_in_trash(kind))?;
+
+        match trashed.row {
+            memory::TrashedRow::Student(student) => {
+                if !tables.courses.iter().any(|c| c.id == student.course_id) {
+                    return Err(AppError::Conflict(COURSE_IS_TRASHED.to_string()));
+                }
+
+                if tables
+                    .students
+                    .iter()
+                    .any(|s| s.email.to_lowercase() == student.email.to_lowercase())
+                {
+                    return Err(memory::unique_violation("student_email_key"));
+                }
+
+                let (avatars, trash) = std::mem::take(&mut tables.trash)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|t| {
+                        t.deleted_at == trashed.deleted_at
+                            && matches!(&t.row, memory::TrashedRow::Avatar(a) if a.student_id == student.id)
+                    });
+                tables.trash = trash;
+                tables
+                    .avatars
+                    .extend(avatars.into_iter().filter_map(|t| match t.row {
+                        memory::TrashedRow::Avatar(avatar) => Some(avatar),
+                        _ => None,
+                    }));
+                tables.students.push(student);
+            }
+            memory::TrashedRow::Avatar(avatar) => {
+                if !tables.students.iter().any(|s| s.id == avatar.student_id) {
+                    return Err(AppError::Conflict(STUDENT_IS_TRASHED.to_string()));
+                }
+
+                if tables
+                    .avatars
+                    .iter()
+                    .any(|a| a.student_id == avatar.student_id)
+                {
+                    return Err(memory::unique_violation("avatar_student_id_key"));
+                }
+
+                tables.avatars.push(avatar);
+            }
+            memory::TrashedRow::Course(course) => {
+                if tables
+                    .courses
+                    .iter()
+                    .any(|c| c.name.to_lowercase() == course.name.to_lowercase())
+                {
+                    return Err(memory::unique_violation("course_name_key"));
+                }
+
+                tables.courses.push(course);
+            }
+            memory::TrashedRow::Subject(subject) => {
+                if tables.subjects.iter().any(|s| {
+                    s.code.to_lowercase() == subject.code.to_lowercase()
+                        && s.program == subject.program
+                }) {
+                    return Err(memory::unique_violation("subject_code_key"));
+                }
+
+                tables.subjects.push(subject);
+            }
+        }
+
+        tables
+            .trash
+            .retain(|t| !(t.row.kind() == kind && t.row.id() == id));
+
+        Ok(())
+    }
+
+    async fn purge(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        InMemoryRepository::purge_row(&mut self.database.write(), kind, id)
+    }
+
+    async fn purge_deleted_before(&self, cutoff: OffsetDateTime) -> AppResult<u64> {
+        let mut tables = self.database.write();
+
+        let mut expired: Vec<_> = tables
+            .trash
+            .iter()
+            .filter(|t| t.deleted_at < cutoff)
+            .map(|t| (t.row.kind(), t.row.id().to_string()))
+            .collect();
+        // Same order as the Postgres purge, courses go last so their students are gone first.
+        expired.sort_by_key(|(kind, _)| match kind {
+            TrashKind::Avatar => 0,
+            TrashKind::Subject => 1,
+            TrashKind::Student => 2,
+            TrashKind::Course => 3,
+        });
+
+        let mut purged = 0;
+        for (kind, id) in expired {
+            match InMemoryRepository::purge_row(&mut tables, kind, &id) {
+                Ok(()) => purged += 1,
+                // A course that still has students waits for a later run.
+                Err(AppError::NotFound(_) | AppError::Conflict(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(purged)
+    }
+}
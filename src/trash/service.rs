@@ -0,0 +1,50 @@
+use std::{sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use tracing::instrument;
+
+use crate::error::AppResult;
+
+use super::{
+    model::{TrashKind, TrashedItem},
+    repository::Repository,
+};
+
+pub struct Service {
+    repository: Arc<dyn Repository>,
+}
+
+impl Service {
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Service { repository }
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub async fn list(&self) -> AppResult<Vec<TrashedItem>> {
+        self.repository.list().await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn restore(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        self.repository.restore(kind, id).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub async fn purge(&self, kind: TrashKind, id: &str) -> AppResult<()> {
+        self.repository.purge(kind, id).await
+    }
+
+    /// Purges what has been in the trash for longer than `retention`, nothing is old enough when
+    /// the retention reaches back before the earliest representable date.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn purge_expired(&self, retention: Duration) -> AppResult<u64> {
+        let cutoff = time::Duration::try_from(retention)
+            .ok()
+            .and_then(|retention| OffsetDateTime::now_utc().checked_sub(retention));
+
+        match cutoff {
+            Some(cutoff) => self.repository.purge_deleted_before(cutoff).await,
+            None => Ok(0),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::Serialize;
+
+/// Checks the fields of a submitted form or payload before it reaches the services.
+pub trait Validate {
+    fn validate(&self) -> Result<(), FieldErrors>;
+}
+
+/// Message of every invalid field, keyed by the name of the field in the form.
+///
+/// Only the first problem of a field is kept, it is the one shown next to the input.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(transparent)]
+pub struct FieldErrors(BTreeMap<&'static str, String>);
+
+impl FieldErrors {
+    pub fn new() -> Self {
+        FieldErrors::default()
+    }
+
+    pub fn add(&mut self, field: &'static str, message: impl Into<String>) -> &mut Self {
+        self.0.entry(field).or_insert_with(|| message.into());
+        self
+    }
+
+    pub fn required(&mut self, field: &'static str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.add(field, "is required");
+        }
+        self
+    }
+
+    pub fn max_length(&mut self, field: &'static str, value: &str, max: usize) -> &mut Self {
+        if value.chars().count() > max {
+            self.add(field, format!("must have at most {max} characters"));
+        }
+        self
+    }
+
+    pub fn email(&mut self, field: &'static str, value: &str) -> &mut Self {
+        if !value.trim().is_empty() && !is_email(value) {
+            self.add(field, "is not a valid email");
+        }
+        self
+    }
+
+    pub fn one_of(&mut self, field: &'static str, value: &str, options: &[&str]) -> &mut Self {
+        if !options.contains(&value) {
+            self.add(field, format!("must be one of {}", options.join(", ")));
+        }
+        self
+    }
+
+    pub fn all_of(
+        &mut self,
+        field: &'static str,
+        values: &[String],
+        options: &[&str],
+    ) -> &mut Self {
+        if let Some(value) = values
+            .iter()
+            .find(|value| !options.contains(&value.as_str()))
+        {
+            self.add(
+                field,
+                format!("{value} is not one of {}", options.join(", ")),
+            );
+        }
+        self
+    }
+
+    pub fn into_result(self) -> Result<(), FieldErrors> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl Display for FieldErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .0
+            .iter()
+            .map(|(field, message)| format!("{field} {message}"))
+            .collect();
+
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+/// Live record that already holds the unique value of a field, the form error links to its
+/// page at `/<entity>/<id>`.
+#[derive(Debug, Serialize)]
+pub struct TakenBy {
+    pub entity: &'static str,
+    pub id: String,
+    pub label: String,
+}
+
+/// Loose check that catches typos, the address is only known to work once a mail arrives.
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && !domain.contains('@')
+        && !value.chars().any(char::is_whitespace)
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty())
+}
@@ -1,37 +1,123 @@
-use std::fs;
+use std::path::PathBuf;
+#[cfg(debug_assertions)]
+use std::{fs, path::Path};
 
-use minijinja::render;
+use minijinja::{context, value::Value, AutoEscape, Environment};
 use serde::Serialize;
 
-pub fn get_template(path: &str) -> Result<&'static str, String> {
-    let def_path = format!("src/view/{path}.jinja");
+#[cfg(debug_assertions)]
+use crate::error::AppError;
+use crate::error::AppResult;
 
-    let result = fs::read(def_path);
+/// Group of pages listed in the navigation bar and on the menu.
+#[derive(Serialize)]
+pub struct NavSection {
+    pub title: &'static str,
+    pub links: &'static [NavLink],
+}
+
+#[derive(Serialize)]
+pub struct NavLink {
+    pub label: &'static str,
+    pub href: &'static str,
+}
+
+/// Compiled templates shared by every request.
+///
+/// Debug builds load the templates from the configured directory and reload them when a file
+/// changes, release builds use the copies embedded at compile time by `build.rs`.
+pub struct Views {
+    #[cfg(debug_assertions)]
+    dir: PathBuf,
+    #[cfg(debug_assertions)]
+    reloader: minijinja_autoreload::AutoReloader,
+    #[cfg(not(debug_assertions))]
+    env: Environment<'static>,
+}
 
-    if let Err(s) = result {
-        return Err(s.to_string());
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    // Every template renders HTML, the default callback only escapes `.html` files.
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
+    env.add_global("navigation", Value::from_serialize(crate::NAVIGATION));
+    env
+}
+
+impl Views {
+    #[cfg(debug_assertions)]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let watched = dir.clone();
+        let reloader = minijinja_autoreload::AutoReloader::new(move |notifier| {
+            let mut env = environment();
+            env.set_loader(minijinja::path_loader(&watched));
+            // Tests build a router per case, watching the directory for each one is wasteful.
+            if cfg!(not(test)) {
+                notifier.watch_path(&watched, true);
+            }
+            Ok(env)
+        });
+
+        Views { dir, reloader }
     }
 
-    let content = result.unwrap();
+    #[cfg(not(debug_assertions))]
+    pub fn new(_dir: impl Into<PathBuf>) -> Self {
+        let mut env = environment();
+        minijinja_embed::load_templates!(env);
 
-    let raw_html = String::from_utf8(content);
+        Views { env }
+    }
 
-    let static_content = match raw_html {
-        Ok(html) => html,
-        Err(e) => {
-            return Err(e.to_string());
+    /// Compiles every template, so a broken one fails the readiness probe instead of a page.
+    #[cfg(debug_assertions)]
+    pub fn check(&self) -> AppResult<()> {
+        let env = self.reloader.acquire_env()?;
+        for name in template_names(&self.dir, &self.dir)? {
+            env.get_template(&name)?;
         }
-    };
-    let static_content: &'static str = Box::leak(static_content.into_boxed_str());
-    Ok(static_content)
+
+        Ok(())
+    }
+
+    /// Embedded templates are compiled when the environment is built, they cannot be broken.
+    #[cfg(not(debug_assertions))]
+    pub fn check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    pub fn render_template<T: Serialize>(
+        &self,
+        template_name: &str,
+        data: Option<T>,
+    ) -> AppResult<String> {
+        #[cfg(debug_assertions)]
+        let env = self.reloader.acquire_env()?;
+        #[cfg(not(debug_assertions))]
+        let env = &self.env;
+
+        let template = env.get_template(&format!("{template_name}.jinja"))?;
+        Ok(template.render(context! { context => data })?)
+    }
 }
 
-pub fn render_template<T: Serialize>(template_name: &str, data: Option<T>) -> String {
-    let template = get_template(template_name)
-        .unwrap_or_else(|_| panic!("Failed to get template: {}", template_name));
+#[cfg(debug_assertions)]
+fn template_names(root: &Path, dir: &Path) -> AppResult<Vec<String>> {
+    let read_error = |err: std::io::Error| AppError::Internal(format!("{}: {err}", dir.display()));
+    let mut names = vec![];
 
-    match data {
-        Some(context) => render!(template, context),
-        None => render!(template),
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_dir() {
+            names.extend(template_names(root, &path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "jinja")
+        {
+            let name = path.strip_prefix(root).unwrap_or(&path);
+            names.push(name.to_string_lossy().replace('\\', "/"));
+        }
     }
+
+    Ok(names)
 }